@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+
+use bevy::{audio::Volume, prelude::*};
+use rand::prelude::*;
+
+use crate::{
+    data::Settings,
+    game::{ImpactEvent, PlayerDeathEvent, RunData},
+    AppState, SimulationState,
+};
+
+// ==== CONSTANTS ====
+/// impacts quieter than this (e.g. standing on the floor) aren't played
+pub const IMPACT_MIN_VOLUME: f32 = 0.1;
+const IMPACT_SOUNDS: [&str; 5] = [
+    "audio/impactGeneric_light_000.ogg",
+    "audio/impactGeneric_light_001.ogg",
+    "audio/impactGeneric_light_002.ogg",
+    "audio/impactGeneric_light_003.ogg",
+    "audio/impactGeneric_light_004.ogg",
+];
+const COIN_SOUNDS: [&str; 3] = [
+    "audio/coin_000.ogg",
+    "audio/coin_001.ogg",
+    "audio/coin_002.ogg",
+];
+const DOOR_SOUNDS: [&str; 2] = [
+    "audio/door_000.ogg",
+    "audio/door_001.ogg",
+];
+const DEATH_SOUNDS: [&str; 2] = [
+    "audio/death_000.ogg",
+    "audio/death_001.ogg",
+];
+const CLICK_SOUNDS: [&str; 2] = [
+    "audio/click_000.ogg",
+    "audio/click_001.ogg",
+];
+/// default tracks, relative to the assets directory
+pub const MUSIC_MENU: &str = "music/menu.ogg";
+pub const MUSIC_LEVELS: &str = "music/levels.ogg";
+pub const MUSIC_GAME: &str = "music/game.ogg";
+/// time (in seconds) of a crossfade between tracks
+pub const MUSIC_CROSSFADE: f32 = 1.5;
+/// part of the music volume kept while the game is paused
+pub const MUSIC_DUCK: f32 = 0.3;
+
+// ==== PLUGIN ====
+
+/// Plays named sounds sent as PlaySoundEvent, through the volume bus of their category,
+/// and the music of the current state
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //.
+        .add_event::<PlaySoundEvent>()
+        .add_systems(Startup, load_sounds)
+        .add_systems(Update, (queue_gameplay_sounds, play_sounds).chain())
+        .add_systems(Update, (select_music, fade_music).chain())
+        //.
+        ;
+    }
+}
+
+// ==== SOUNDS ====
+
+/// Every sound the game can play
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Sound {
+    Impact,
+    Coin,
+    Door,
+    Death,
+    MenuClick,
+}
+
+/// Volume bus a sound goes through, each has its own volume in settings
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SoundCategory {
+    Sfx,
+    Ui,
+}
+
+impl SoundCategory {
+    pub fn volume(&self, settings: &Settings) -> f32 {
+        match self {
+            SoundCategory::Sfx => settings.sfx_volume,
+            SoundCategory::Ui => settings.ui_volume,
+        }
+    }
+}
+
+/// Variations of a sound, one of them is picked at random every time it plays
+pub struct SoundPool {
+    pub sources: Vec<Handle<AudioSource>>,
+    pub category: SoundCategory,
+    pub volume: f32,
+    /// playback speed (and pitch) is picked from this range
+    pub speed: (f32, f32),
+    /// time (in seconds) before the sound can play again, repeats in between are dropped
+    pub cooldown: f32,
+}
+
+/// Sound pools and when each sound last played
+#[derive(Resource, Default)]
+pub struct AudioManager {
+    pub pools: HashMap<Sound, SoundPool>,
+    last_played: HashMap<Sound, f32>,
+}
+
+impl AudioManager {
+    /// whether the sound is off cooldown, marks it as played if so
+    fn try_play(&mut self, sound: Sound, now: f32) -> bool {
+        let Some(pool) = self.pools.get(&sound) else {return false};
+        match self.last_played.get(&sound) {
+            Some(last) if now - last < pool.cooldown => false,
+            _ => {
+                self.last_played.insert(sound, now);
+                true
+            }
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct PlaySoundEvent {
+    pub sound: Sound,
+    /// 0.0 - 1.0, multiplied by the pool's and the bus' volume
+    pub volume: f32,
+}
+
+impl PlaySoundEvent {
+    pub fn new(sound: Sound) -> Self {
+        PlaySoundEvent { sound, volume: 1.0 }
+    }
+}
+
+// ==== MUSIC ====
+
+/// A looping track, fading in or (when it was replaced) out
+#[derive(Component)]
+pub struct MusicTrack {
+    pub path: String,
+    /// 0.0 - 1.0, current volume of the fade
+    pub fade: f32,
+    pub fading_out: bool,
+}
+
+// ==== SYSTEMS ====
+
+pub fn load_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let pool = |paths: &[&'static str], category, volume, speed, cooldown| SoundPool {
+        sources: paths.iter().map(|s| asset_server.load(*s)).collect(),
+        category,
+        volume,
+        speed,
+        cooldown,
+    };
+    let mut manager = AudioManager::default();
+    manager.pools.insert(Sound::Impact, pool(&IMPACT_SOUNDS, SoundCategory::Sfx, 1.0, (0.9, 1.1), 0.08));
+    manager.pools.insert(Sound::Coin, pool(&COIN_SOUNDS, SoundCategory::Sfx, 0.6, (0.98, 1.02), 0.03));
+    manager.pools.insert(Sound::Door, pool(&DOOR_SOUNDS, SoundCategory::Sfx, 0.8, (1.0, 1.0), 0.5));
+    manager.pools.insert(Sound::Death, pool(&DEATH_SOUNDS, SoundCategory::Sfx, 0.8, (0.95, 1.05), 0.5));
+    manager.pools.insert(Sound::MenuClick, pool(&CLICK_SOUNDS, SoundCategory::Ui, 0.5, (1.0, 1.0), 0.05));
+    commands.insert_resource(manager);
+}
+
+/// Sounds of gameplay events which have no sound of their own
+pub fn queue_gameplay_sounds(
+    mut impact_reader: EventReader<ImpactEvent>,
+    mut death_reader: EventReader<PlayerDeathEvent>,
+    mut event_writer: EventWriter<PlaySoundEvent>,
+) {
+    // only the hardest impact of a frame is heard
+    let strength = impact_reader.read().map(|e| e.strength).fold(0.0, f32::max);
+    if strength >= IMPACT_MIN_VOLUME {
+        event_writer.send(PlaySoundEvent { sound: Sound::Impact, volume: strength.min(1.0) });
+    }
+    for _ in death_reader.read() {
+        event_writer.send(PlaySoundEvent::new(Sound::Death));
+    }
+}
+
+pub fn play_sounds(
+    mut event_reader: EventReader<PlaySoundEvent>,
+    mut commands: Commands,
+    manager: Option<ResMut<AudioManager>>,
+    settings: Res<Settings>,
+    time: Res<Time<Real>>,
+) {
+    let Some(mut manager) = manager else {return};
+    let now = time.elapsed_seconds();
+    for event in event_reader.read() {
+        if !manager.try_play(event.sound, now) {continue;}
+        let pool = &manager.pools[&event.sound];
+        let Some(source) = pool.sources.choose(&mut thread_rng()) else {continue};
+        let volume = event.volume * pool.volume * pool.category.volume(&settings);
+        let speed = thread_rng().gen_range(pool.speed.0..=pool.speed.1);
+        commands.spawn(AudioBundle {
+            source: source.clone(),
+            settings: PlaybackSettings::DESPAWN
+                .with_volume(Volume::new(volume))
+                .with_speed(speed),
+        });
+    }
+}
+
+/// Track for the current state, the run's own track in game
+fn wanted_music(app_state: &AppState, run: &RunData) -> String {
+    match app_state {
+        AppState::Levels => MUSIC_LEVELS.to_string(),
+        AppState::Game => run.music.clone().unwrap_or(MUSIC_GAME.to_string()),
+        _ => MUSIC_MENU.to_string(),
+    }
+}
+
+/// Start the track of the current state, fading out the previous one
+pub fn select_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    app_state: Res<State<AppState>>,
+    run_resource: Res<RunData>,
+    mut track_query: Query<&mut MusicTrack>,
+) {
+    if !app_state.is_changed() && !run_resource.is_changed() {return;}
+    let wanted = wanted_music(app_state.get(), &run_resource);
+    let mut playing = false;
+    for mut track in track_query.iter_mut() {
+        if track.path == wanted {
+            // came back to a track before it faded out
+            track.fading_out = false;
+            playing = true;
+        } else {
+            track.fading_out = true;
+        }
+    }
+    if playing {return;}
+    // missing music isn't an error, the game just stays quiet
+    if !std::path::Path::new("assets").join(&wanted).exists() {
+        println!("[MUSIC] No track at assets/{}", wanted);
+        return;
+    }
+    println!("[MUSIC] Playing {}", wanted);
+    commands.spawn((
+        AudioBundle {
+            source: asset_server.load::<AudioSource>(wanted.clone()),
+            settings: PlaybackSettings::LOOP.with_volume(Volume::new(0.0)),
+        },
+        MusicTrack { path: wanted, fade: 0.0, fading_out: false },
+    ));
+}
+
+/// Crossfade tracks and keep them at the music volume, lowered while paused
+pub fn fade_music(
+    mut commands: Commands,
+    mut track_query: Query<(Entity, &mut MusicTrack, Option<&AudioSink>)>,
+    settings: Res<Settings>,
+    simulation_state: Res<State<SimulationState>>,
+    app_state: Res<State<AppState>>,
+    time: Res<Time<Real>>,
+) {
+    let step = time.delta_seconds() / MUSIC_CROSSFADE;
+    let paused = *app_state.get() == AppState::Game && *simulation_state.get() == SimulationState::Paused;
+    let duck = if paused {MUSIC_DUCK} else {1.0};
+    for (entity, mut track, sink) in track_query.iter_mut() {
+        let step = if track.fading_out {-step} else {step};
+        track.fade = (track.fade + step).clamp(0.0, 1.0);
+        if track.fading_out && track.fade <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        // the sink only exists once the track started playing
+        if let Some(sink) = sink {
+            sink.set_volume(track.fade * duck * settings.music_volume * settings.master_volume);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::audio::Decodable;
+
+    use super::*;
+
+    #[test]
+    fn default_tracks_decode() {
+        for path in [MUSIC_MENU, MUSIC_LEVELS, MUSIC_GAME] {
+            let bytes = std::fs::read(std::path::Path::new("assets").join(path)).expect(path);
+            let source = AudioSource { bytes: bytes.into() };
+            assert!(source.decoder().count() > 0, "{} has no samples", path);
+        }
+    }
+
+    #[test]
+    fn sounds_decode() {
+        let sounds = IMPACT_SOUNDS.iter().chain(&COIN_SOUNDS).chain(&DOOR_SOUNDS).chain(&DEATH_SOUNDS).chain(&CLICK_SOUNDS);
+        for path in sounds {
+            let bytes = std::fs::read(std::path::Path::new("assets").join(path)).expect(path);
+            let source = AudioSource { bytes: bytes.into() };
+            assert!(source.decoder().count() > 0, "{} has no samples", path);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use bevy::{
+    prelude::*,
+    render::{camera::{ScalingMode, Viewport}, view::RenderLayers},
+    window::{PrimaryWindow, WindowResized},
+};
+
+use rand::random;
+
+use crate::{data::LevelBounds, game::PlayerComponent, AppState};
+
+// ==== CONSTANTS ====
+/// size of the logical playfield, levels are laid out in this space
+pub const PLAYFIELD_WIDTH: f32 = 1280.0;
+pub const PLAYFIELD_HEIGHT: f32 = 960.0;
+pub const LETTERBOX_COLOR: Color = Color::BLACK;
+/// how far (from the center) the player can move before the camera follows
+pub const CAMERA_DEAD_ZONE: Vec2 = Vec2::new(160.0, 120.0);
+/// how quickly the camera catches up, higher is faster
+pub const CAMERA_SMOOTHING: f32 = 8.0;
+/// offset (in px) of the strongest shake
+pub const SHAKE_MAX_OFFSET: f32 = 16.0;
+/// trauma lost per second
+pub const SHAKE_DECAY: f32 = 2.5;
+
+// ==== PLUGIN ====
+
+/// Shows the playfield scaled to fit the window, with bars filling the rest.<br>
+/// In game the camera follows the player within the level's bounds.
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //.
+        .add_systems(Startup, spawn_camera)
+        .init_resource::<ScreenShake>()
+        .add_systems(Update, (
+            follow_player.run_if(in_state(AppState::Game)),
+            apply_screen_shake
+        ).chain())
+        .add_systems(PostUpdate, fit_camera_to_window)
+        //.
+        ;
+    }
+}
+
+// ==== COMPONENTS ====
+
+/// Camera showing the playfield
+#[derive(Component)]
+pub struct MainCamera;
+
+/// Point the camera looks at, before shaking
+#[derive(Component)]
+pub struct CameraFocus(pub Vec2);
+
+// ==== RESOURCES ====
+
+/// Shake of the camera, 0.0 - 1.0, the offset grows with its square
+#[derive(Resource, Default)]
+pub struct ScreenShake {
+    pub trauma: f32,
+}
+
+impl ScreenShake {
+    pub fn add(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+}
+
+// ==== SYSTEMS ====
+
+pub fn spawn_camera(mut commands: Commands) {
+    // only clears the window, so the bars around the viewport stay black
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                order: -1,
+                clear_color: ClearColorConfig::Custom(LETTERBOX_COLOR),
+                ..default()
+            },
+            ..default()
+        },
+        RenderLayers::none(),
+    ));
+    let center = Vec2::new(PLAYFIELD_WIDTH / 2.0, PLAYFIELD_HEIGHT / 2.0);
+    let mut camera = Camera2dBundle {
+        transform: Transform::from_xyz(center.x, center.y, 0.0),
+        ..default()
+    };
+    camera.projection.scaling_mode = ScalingMode::Fixed {
+        width: PLAYFIELD_WIDTH,
+        height: PLAYFIELD_HEIGHT,
+    };
+    commands.spawn((camera, MainCamera, CameraFocus(center), IsDefaultUiCamera));
+    println!("[INFO] Playfield size {}x{} , spawning camera in center",PLAYFIELD_WIDTH,PLAYFIELD_HEIGHT);
+}
+
+/// Keep the viewport the largest area with the playfield's aspect ratio, centered in the window.<br>
+/// UI is scaled with it, so menus keep their size relative to the playfield.
+pub fn fit_camera_to_window(
+    mut resize_events: EventReader<WindowResized>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<(&mut Camera, Ref<MainCamera>)>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    // fit when the window changes and when the camera was just spawned
+    let resized = resize_events.read().count() > 0;
+    let spawned = camera_query.iter().any(|(_, marker)| marker.is_added());
+    if !resized && !spawned {return;}
+    let Ok(window) = window_query.get_single() else {return};
+    let physical = Vec2::new(window.physical_width() as f32, window.physical_height() as f32);
+    if physical.x <= 0.0 || physical.y <= 0.0 {return;}
+    let scale = f32::min(physical.x / PLAYFIELD_WIDTH, physical.y / PLAYFIELD_HEIGHT);
+    let size = Vec2::new(PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT) * scale;
+    let position = (physical - size) / 2.0;
+    for (mut camera, _) in camera_query.iter_mut() {
+        camera.viewport = Some(Viewport {
+            physical_position: position.as_uvec2(),
+            physical_size: size.as_uvec2().max(UVec2::ONE),
+            ..default()
+        });
+    }
+    // menus were laid out for a 1280x960 window
+    ui_scale.0 = scale / window.scale_factor();
+}
+
+/// Move the camera once the player leaves the dead zone, easing towards them.<br>
+/// The view never shows anything outside the level's bounds and snaps to the player when a level gets loaded.
+pub fn follow_player(
+    mut camera_query: Query<&mut CameraFocus, With<MainCamera>>,
+    player_query: Query<&Transform, With<PlayerComponent>>,
+    level_bounds: Res<LevelBounds>,
+    time: Res<Time>,
+) {
+    let (Ok(mut camera), Ok(player)) = (camera_query.get_single_mut(), player_query.get_single()) else {return};
+    let player = player.translation.xy();
+    let mut target = camera.0;
+    // keep the player within the dead zone
+    let offset = player - target;
+    if offset.x.abs() > CAMERA_DEAD_ZONE.x {
+        target.x = player.x - offset.x.signum() * CAMERA_DEAD_ZONE.x;
+    }
+    if offset.y.abs() > CAMERA_DEAD_ZONE.y {
+        target.y = player.y - offset.y.signum() * CAMERA_DEAD_ZONE.y;
+    }
+    let position = if level_bounds.is_changed() {
+        clamp_to_bounds(player, level_bounds.0)
+    } else {
+        let target = clamp_to_bounds(target, level_bounds.0);
+        let t = 1.0 - (-CAMERA_SMOOTHING * time.delta_seconds()).exp();
+        camera.0.lerp(target, t)
+    };
+    camera.0 = position;
+}
+
+/// Place the camera at its focus, offset randomly while shaking
+pub fn apply_screen_shake(
+    mut camera_query: Query<(&mut Transform, &CameraFocus), With<MainCamera>>,
+    mut shake: ResMut<ScreenShake>,
+    time: Res<Time>,
+) {
+    let Ok((mut transform, focus)) = camera_query.get_single_mut() else {return};
+    let strength = shake.trauma * shake.trauma * SHAKE_MAX_OFFSET;
+    let offset = Vec2::new(random::<f32>() * 2.0 - 1.0, random::<f32>() * 2.0 - 1.0) * strength;
+    transform.translation.x = focus.0.x + offset.x;
+    transform.translation.y = focus.0.y + offset.y;
+    if shake.trauma > 0.0 {
+        shake.trauma = (shake.trauma - SHAKE_DECAY * time.delta_seconds()).max(0.0);
+    }
+}
+
+/// closest camera position which shows only the inside of bounds,
+/// bounds smaller than the playfield are centered
+pub fn clamp_to_bounds(position: Vec2, bounds: Rect) -> Vec2 {
+    let half = Vec2::new(PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT) / 2.0;
+    let clamp = |v: f32, min: f32, max: f32, half: f32| {
+        if max - min <= half * 2.0 {(min + max) / 2.0} else {v.clamp(min + half, max - half)}
+    };
+    Vec2::new(
+        clamp(position.x, bounds.min.x, bounds.max.x, half.x),
+        clamp(position.y, bounds.min.y, bounds.max.y, half.y),
+    )
+}
@@ -0,0 +1,319 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{Read, Write},
+};
+
+use bevy::{
+    prelude::*,
+    reflect::{DynamicEnum, FromReflect, TypeInfo, Typed, VariantInfo},
+};
+
+pub const PATH_CONTROLS: &str = "./config/controls";
+/// how far a stick has to be tilted to count as a direction
+pub const STICK_DEADZONE: f32 = 0.5;
+
+// ==== PLUGIN ====
+
+pub struct BindingsPlugin;
+
+impl Plugin for BindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //.
+        .insert_resource(InputBindings::load(PATH_CONTROLS))
+        //.
+        ;
+    }
+}
+
+// ==== ACTIONS ====
+
+/// Everything that can be bound to a key
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum InputAction {
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Dash,
+    GroundPound,
+    Pause,
+    MenuUp,
+    MenuDown,
+    MenuConfirm,
+    MenuBack,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 10] = [
+        InputAction::MoveLeft,
+        InputAction::MoveRight,
+        InputAction::Jump,
+        InputAction::Dash,
+        InputAction::GroundPound,
+        InputAction::Pause,
+        InputAction::MenuUp,
+        InputAction::MenuDown,
+        InputAction::MenuConfirm,
+        InputAction::MenuBack,
+    ];
+
+    /// name used in the controls file
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputAction::MoveLeft => "MOVE_LEFT",
+            InputAction::MoveRight => "MOVE_RIGHT",
+            InputAction::Jump => "JUMP",
+            InputAction::Dash => "DASH",
+            InputAction::GroundPound => "GROUND_POUND",
+            InputAction::Pause => "PAUSE",
+            InputAction::MenuUp => "MENU_UP",
+            InputAction::MenuDown => "MENU_DOWN",
+            InputAction::MenuConfirm => "MENU_CONFIRM",
+            InputAction::MenuBack => "MENU_BACK",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<InputAction> {
+        InputAction::ALL.into_iter().find(|a| a.name() == name)
+    }
+
+    /// name shown in the controls menu
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputAction::MoveLeft => "Left",
+            InputAction::MoveRight => "Right",
+            InputAction::Jump => "Jump",
+            InputAction::Dash => "Dash",
+            InputAction::GroundPound => "Pound",
+            InputAction::Pause => "Pause",
+            InputAction::MenuUp => "Menu up",
+            InputAction::MenuDown => "Menu down",
+            InputAction::MenuConfirm => "Confirm",
+            InputAction::MenuBack => "Back",
+        }
+    }
+
+    /// used in menus, their keys may be shared with the ones used in game
+    pub fn is_menu(&self) -> bool {
+        matches!(self, InputAction::MenuUp | InputAction::MenuDown | InputAction::MenuConfirm | InputAction::MenuBack)
+    }
+}
+
+// ==== RESOURCE ====
+
+/// Keys and gamepad buttons bound to every action
+#[derive(Resource, Clone)]
+pub struct InputBindings {
+    pub keys: HashMap<InputAction, Vec<KeyCode>>,
+    pub buttons: HashMap<InputAction, Vec<GamepadButtonType>>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        InputBindings {
+            keys: HashMap::from([
+                (InputAction::MoveLeft, vec![KeyCode::KeyA, KeyCode::ArrowLeft]),
+                (InputAction::MoveRight, vec![KeyCode::KeyD, KeyCode::ArrowRight]),
+                (InputAction::Jump, vec![KeyCode::Space, KeyCode::ArrowUp]),
+                (InputAction::Dash, vec![KeyCode::ShiftLeft]),
+                (InputAction::GroundPound, vec![KeyCode::KeyS, KeyCode::ArrowDown]),
+                (InputAction::Pause, vec![KeyCode::Escape]),
+                (InputAction::MenuUp, vec![KeyCode::ArrowUp, KeyCode::ArrowLeft]),
+                (InputAction::MenuDown, vec![KeyCode::ArrowDown, KeyCode::ArrowRight, KeyCode::Tab]),
+                (InputAction::MenuConfirm, vec![KeyCode::Enter, KeyCode::NumpadEnter]),
+                (InputAction::MenuBack, vec![KeyCode::Escape]),
+            ]),
+            buttons: HashMap::from([
+                (InputAction::MoveLeft, vec![GamepadButtonType::DPadLeft]),
+                (InputAction::MoveRight, vec![GamepadButtonType::DPadRight]),
+                (InputAction::Jump, vec![GamepadButtonType::South]),
+                (InputAction::Dash, vec![GamepadButtonType::West, GamepadButtonType::RightTrigger]),
+                (InputAction::GroundPound, vec![GamepadButtonType::DPadDown]),
+                (InputAction::Pause, vec![GamepadButtonType::Start]),
+                (InputAction::MenuUp, vec![GamepadButtonType::DPadUp]),
+                (InputAction::MenuDown, vec![GamepadButtonType::DPadDown]),
+                (InputAction::MenuConfirm, vec![GamepadButtonType::South]),
+                (InputAction::MenuBack, vec![GamepadButtonType::East]),
+            ]),
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn keys(&self, action: InputAction) -> &[KeyCode] {
+        match self.keys.get(&action) {
+            Some(keys) => keys,
+            None => &[],
+        }
+    }
+
+    pub fn pressed(&self, action: InputAction, keyboard: &ButtonInput<KeyCode>) -> bool {
+        keyboard.any_pressed(self.keys(action).iter().copied())
+    }
+
+    pub fn just_pressed(&self, action: InputAction, keyboard: &ButtonInput<KeyCode>) -> bool {
+        keyboard.any_just_pressed(self.keys(action).iter().copied())
+    }
+
+    pub fn just_released(&self, action: InputAction, keyboard: &ButtonInput<KeyCode>) -> bool {
+        self.keys(action).iter().any(|k| keyboard.just_released(*k))
+    }
+
+    pub fn buttons(&self, action: InputAction) -> &[GamepadButtonType] {
+        match self.buttons.get(&action) {
+            Some(buttons) => buttons,
+            None => &[],
+        }
+    }
+
+    /// every bound button of every connected gamepad
+    fn pad_buttons<'a>(&'a self, action: InputAction, gamepads: &'a Gamepads) -> impl Iterator<Item = GamepadButton> + 'a {
+        gamepads.iter().flat_map(move |pad| {
+            self.buttons(action).iter().map(move |b| GamepadButton::new(pad, *b))
+        })
+    }
+
+    pub fn pad_pressed(&self, action: InputAction, gamepads: &Gamepads, pad_input: &ButtonInput<GamepadButton>) -> bool {
+        pad_input.any_pressed(self.pad_buttons(action, gamepads))
+    }
+
+    pub fn pad_just_pressed(&self, action: InputAction, gamepads: &Gamepads, pad_input: &ButtonInput<GamepadButton>) -> bool {
+        pad_input.any_just_pressed(self.pad_buttons(action, gamepads))
+    }
+
+    pub fn pad_just_released(&self, action: InputAction, gamepads: &Gamepads, pad_input: &ButtonInput<GamepadButton>) -> bool {
+        self.pad_buttons(action, gamepads).any(|b| pad_input.just_released(b))
+    }
+
+    /// Replace every key of an action with the given one, returns the action of the same kind which had it.<br>
+    /// That action loses the key, if it has none left it takes the rebound action's old keys instead.
+    pub fn rebind(&mut self, action: InputAction, key: KeyCode) -> Option<InputAction> {
+        let previous = self.keys.insert(action, vec![key]).unwrap_or_default();
+        let other = InputAction::ALL.into_iter()
+            .find(|a| *a != action && a.is_menu() == action.is_menu() && self.keys(*a).contains(&key))?;
+        let keys = self.keys.entry(other).or_default();
+        keys.retain(|k| *k != key);
+        if keys.is_empty() {
+            *keys = previous.into_iter().filter(|k| *k != key).collect();
+        }
+        Some(other)
+    }
+
+    /// keys of an action in a readable form
+    pub fn describe(&self, action: InputAction) -> String {
+        let keys: Vec<String> = self.keys(action).iter().map(|k| format!("{:?}", k)).collect();
+        if keys.is_empty() {"-".to_string()} else {keys.join(" / ")}
+    }
+
+    /// load bindings from a file, actions missing from it keep their default keys
+    pub fn load(path: &str) -> InputBindings {
+        let mut bindings = InputBindings::default();
+        let mut buff = String::new();
+        match File::open(path) {
+            Ok(mut f) => {
+                if let Err(e) = f.read_to_string(&mut buff) {
+                    println!("[BINDINGS] Couldn't read {} due {}", path, e);
+                }
+            }
+            Err(_) => {
+                println!("[BINDINGS] No controls file at {}, using defaults", path);
+                return bindings;
+            }
+        }
+        for (n, line) in buff.lines().enumerate() {
+            let segs: Vec<&str> = line.split_ascii_whitespace().collect();
+            if segs.is_empty() {
+                continue;
+            }
+            // gamepad buttons are stored under PAD_<action>
+            let (pad, name) = match segs[0].strip_prefix("PAD_") {
+                Some(name) => (true, name),
+                None => (false, segs[0]),
+            };
+            match InputAction::from_name(name) {
+                Some(action) if pad => {
+                    let buttons = segs[1..].iter().filter_map(|b| variant_from_name::<GamepadButtonType>(b)).collect();
+                    bindings.buttons.insert(action, buttons);
+                }
+                Some(action) => {
+                    let keys = segs[1..].iter().filter_map(|k| variant_from_name::<KeyCode>(k)).collect();
+                    bindings.keys.insert(action, keys);
+                }
+                None => println!("[BINDINGS] Error at line number {}: Unknown action {}", n, segs[0]),
+            }
+        }
+        println!("[BINDINGS] Loaded controls from {}", path);
+        bindings
+    }
+
+    pub fn save(&self, path: &str) {
+        let mut buff = String::new();
+        for action in InputAction::ALL {
+            buff += action.name();
+            for key in self.keys(action) {
+                buff += &format!(" {:?}", key);
+            }
+            buff += "\n";
+        }
+        for action in InputAction::ALL {
+            buff += &format!("PAD_{}", action.name());
+            for button in self.buttons(action) {
+                buff += &format!(" {:?}", button);
+            }
+            buff += "\n";
+        }
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        match File::create(path).and_then(|mut f| f.write_all(buff.as_bytes())) {
+            Ok(_) => println!("[BINDINGS] Saved controls to {}", path),
+            Err(e) => println!("[BINDINGS] Couldn't save controls to {} due {}", path, e),
+        }
+    }
+}
+
+/// KeyCode or GamepadButtonType from its variant name (e.g. KeyA, Space, South)
+pub fn variant_from_name<T: Typed + FromReflect>(name: &str) -> Option<T> {
+    // only unit variants can be built from a name alone
+    let known = match T::type_info() {
+        TypeInfo::Enum(info) => matches!(info.variant(name), Some(VariantInfo::Unit(_))),
+        _ => false,
+    };
+    if !known {
+        println!("[BINDINGS] Unknown key or button {}", name);
+        return None;
+    }
+    T::from_reflect(&DynamicEnum::new(name, ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebind_takes_key_from_other_action() {
+        let mut bindings = InputBindings::default();
+        assert_eq!(bindings.rebind(InputAction::Jump, KeyCode::KeyA), Some(InputAction::MoveLeft));
+        assert_eq!(bindings.keys(InputAction::Jump), [KeyCode::KeyA]);
+        assert_eq!(bindings.keys(InputAction::MoveLeft), [KeyCode::ArrowLeft]);
+    }
+
+    #[test]
+    fn rebind_swaps_last_key() {
+        let mut bindings = InputBindings::default();
+        assert_eq!(bindings.rebind(InputAction::Dash, KeyCode::Escape), Some(InputAction::Pause));
+        assert_eq!(bindings.keys(InputAction::Dash), [KeyCode::Escape]);
+        assert_eq!(bindings.keys(InputAction::Pause), [KeyCode::ShiftLeft]);
+    }
+
+    #[test]
+    fn menu_keys_may_be_shared_with_game() {
+        let mut bindings = InputBindings::default();
+        assert_eq!(bindings.rebind(InputAction::MenuConfirm, KeyCode::Space), None);
+        assert_eq!(bindings.keys(InputAction::Jump), [KeyCode::Space, KeyCode::ArrowUp]);
+        assert_eq!(bindings.rebind(InputAction::MenuConfirm, KeyCode::Escape), Some(InputAction::MenuBack));
+        assert_eq!(bindings.keys(InputAction::MenuBack), [KeyCode::Space]);
+        assert_eq!(bindings.keys(InputAction::Pause), [KeyCode::Escape]);
+    }
+}
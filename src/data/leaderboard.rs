@@ -0,0 +1,142 @@
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::{Component, Path},
+};
+
+pub const PATH_LEADERBOARDS: &str = "./config/leaderboards/";
+/// entries kept per run
+pub const LEADERBOARD_SIZE: usize = 5;
+/// longest player name
+pub const PLAYER_NAME_LENGTH: usize = 12;
+
+// ==== LEADERBOARD ====
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    /// time (in seconds) of the run
+    pub time: f32,
+    pub coins: usize,
+    pub deaths: usize,
+    /// file name of the run's replay, in PATH_REPLAYS
+    pub replay: String,
+}
+
+/// Fastest runs of a run, stored next to the others under the run's path
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Leaderboard {
+    /// sorted from the fastest
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    /// directory name of the run at the given path
+    pub fn run_name(run: &str) -> String {
+        Path::new(run).file_name().map_or(run.to_string(), |n| n.to_string_lossy().to_string())
+    }
+
+    /// the whole path of a run as a file name, so runs with the same name in different directories stay apart<br>
+    /// directories are joined with _, anything but letters and digits is escaped as %XX
+    pub fn run_key(run: &str) -> String {
+        let escape = |name: &str| name.bytes()
+            .map(|b| if b.is_ascii_alphanumeric() {(b as char).to_string()} else {format!("%{:02X}", b)})
+            .collect::<String>();
+        Path::new(run).components()
+            .filter_map(|c| match c {
+                Component::CurDir => None,
+                Component::RootDir => Some(escape("/")),
+                other => Some(escape(&other.as_os_str().to_string_lossy())),
+            })
+            .collect::<Vec<String>>()
+            .join("_")
+    }
+
+    pub fn path(run: &str) -> String {
+        format!("{}{}", PATH_LEADERBOARDS, Leaderboard::run_key(run))
+    }
+
+    /// read the leaderboard of the run at the given path, one entry per line:
+    /// ```text
+    /// ENTRY <time> <coins> <deaths> <replay> <name>
+    /// ```
+    pub fn load(run: &str) -> Leaderboard {
+        let path = Leaderboard::path(run);
+        let mut leaderboard = Leaderboard::default();
+        let mut buff = String::new();
+        if File::open(&path).and_then(|mut f| f.read_to_string(&mut buff)).is_err() {
+            // no leaderboard until the run was finished once
+            return leaderboard;
+        }
+        for (n, line) in buff.lines().enumerate() {
+            // the name is the rest of the line, it may contain spaces
+            let segs: Vec<&str> = line.trim().splitn(6, ' ').collect();
+            match segs[..] {
+                [""] => {}
+                ["ENTRY", time, coins, deaths, replay, name] => match (time.parse(), coins.parse(), deaths.parse()) {
+                    (Ok(time), Ok(coins), Ok(deaths)) => leaderboard.entries.push(LeaderboardEntry {
+                        name: name.to_string(),
+                        time,
+                        coins,
+                        deaths,
+                        replay: replay.to_string(),
+                    }),
+                    _ => println!("[LEADERBOARD] Error at line number {} of {}: Invalid entry", n, path),
+                },
+                _ => println!("[LEADERBOARD] Error at line number {} of {}: Unknown line", n, path),
+            }
+        }
+        leaderboard.entries.sort_by(|a, b| a.time.total_cmp(&b.time));
+        leaderboard.entries.truncate(LEADERBOARD_SIZE);
+        leaderboard
+    }
+
+    /// write to a temporary file first and move it over the leaderboard, like the save
+    pub fn save(&self, run: &str) {
+        let path = Leaderboard::path(run);
+        let mut buff = String::new();
+        for entry in self.entries.iter() {
+            buff += &format!(
+                "ENTRY {} {} {} {} {}\n",
+                entry.time, entry.coins, entry.deaths, entry.replay, entry.name
+            );
+        }
+        let _ = fs::create_dir_all(PATH_LEADERBOARDS);
+        let temp = format!("{}.tmp", path);
+        match File::create(&temp)
+            .and_then(|mut f| f.write_all(buff.as_bytes()).and_then(|_| f.sync_all()))
+            .and_then(|_| fs::rename(&temp, &path))
+        {
+            Ok(_) => println!("[LEADERBOARD] Saved to {}", path),
+            Err(e) => println!("[LEADERBOARD] Couldn't save to {} due {}", path, e),
+        }
+    }
+
+    /// place (from 0) a run with the given time would take, none if it's too slow
+    pub fn rank(&self, time: f32) -> Option<usize> {
+        // ties go below the earlier runs
+        let rank = self.entries.iter().filter(|e| e.time <= time).count();
+        (rank < LEADERBOARD_SIZE).then_some(rank)
+    }
+
+    /// add an entry in its place, returns the entries which fell off the table
+    pub fn insert(&mut self, entry: LeaderboardEntry) -> Vec<LeaderboardEntry> {
+        let Some(rank) = self.rank(entry.time) else {return vec![entry]};
+        self.entries.insert(rank, entry);
+        self.entries.split_off(LEADERBOARD_SIZE.min(self.entries.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_are_keyed_by_whole_path() {
+        assert_eq!(Leaderboard::run_key("./assets/levels/og4"), "assets_levels_og4");
+        assert_eq!(Leaderboard::run_key("./assets/levels/og4/"), "assets_levels_og4");
+        assert_ne!(Leaderboard::run_key("./assets/levels/og4"), Leaderboard::run_key("./mods/og4"));
+        assert_ne!(Leaderboard::run_key("a_b/c"), Leaderboard::run_key("a/b_c"));
+        assert_eq!(Leaderboard::run_key("my run/1"), "my%20run_1");
+    }
+}
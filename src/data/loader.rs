@@ -1,26 +1,31 @@
 use std::{
     fmt::Debug,
-    fs::{self, read_dir, DirEntry, File},
+    fs::{self, read_dir, DirEntry, File, ReadDir},
     io::Read,
-    path::Path,
 };
 
 use bevy::{
+    ecs::system::EntityCommands,
     prelude::*,
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
-    utils::thiserror::Error,
 };
 
 use crate::{
+    camera::{PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH},
     game::{
         coin::{CoinComponent, Score},
         door::DoorComponent,
         obstacle::ObstacleComponent,
-        spawn_player, PlayerComponent, Size, Speed,
+        spring::{SpringComponent, SPRING_COLOR},
+        switch::{Inactive, SwitchComponent, SwitchKind, Triggerable, SWITCH_OFF_COLOR},
+        grant_abilities, handle_player_input, spawn_player, Ability, Acceleration, CollisionSides, GravityCounter, JumpLock, JumpTimers, MedalThreshold, PhysicsConfig, PlayerComponent, Size, Speed,
     },
     AppState,
 };
 
+#[cfg(debug_assertions)]
+use super::validate_level;
+
 #[allow(unused)]
 pub const COIN_SIZE: f32 = 20.0;
 
@@ -40,9 +45,27 @@ pub enum NextLevel {
 #[derive(Resource, Default)]
 pub struct RunData {
     pub author: String,
+    /// track played during the run instead of the default one
+    pub music: Option<String>,
     pub len: usize,
     pub next: NextLevel,
     pub path: String,
+    /// target time (in seconds) of each level, set with `PAR <level> <seconds>`
+    pub par: Vec<Option<f32>>,
+    /// set with `MEDAL <medal> <seconds> <coins> <deaths>`
+    pub medals: Vec<MedalThreshold>,
+    /// set with `LEVEL_MEDAL <level> <medal> <seconds> <coins> <deaths>`
+    pub level_medals: Vec<Vec<MedalThreshold>>,
+}
+
+/// Run which gets loaded when entering AppState::Game
+#[derive(Resource)]
+pub struct SelectedRun(pub String);
+
+impl Default for SelectedRun {
+    fn default() -> Self {
+        SelectedRun(format!("{}og4", PATH_LEVELS))
+    }
 }
 
 pub struct LoaderPlugin;
@@ -52,14 +75,18 @@ impl Plugin for LoaderPlugin {
         app
             // events
             .init_resource::<RunData>()
+            .init_resource::<SelectedRun>()
+            .init_resource::<LevelBounds>()
+            .init_resource::<LevelSpawn>()
             .add_event::<LoadRunEvent>()
             .add_event::<LoadLevelEvent>()
             // systems
-            .add_systems(OnEnter(AppState::Game), test_loading.after(spawn_player))
-            .add_systems(Update, (
+            .add_systems(OnEnter(AppState::Game), load_selected_run.after(spawn_player))
+            // loaded on a tick, before the player moves, so every run starts the same
+            .add_systems(FixedUpdate, (
                 handle_loadrunevent,
                 handle_loadlevelevent
-            ).run_if(
+            ).chain().before(handle_player_input).run_if(
                 in_state(AppState::Game)
             ))
             .add_systems( OnExit(AppState::Game), despawn_level)
@@ -84,22 +111,36 @@ pub fn handle_loadrunevent(
     mut event_read: EventReader<LoadRunEvent>,
     mut event_write: EventWriter<LoadLevelEvent>,
     mut run_resource: ResMut<RunData>,
+    mut physics_config: ResMut<PhysicsConfig>,
+    mut commands: Commands,
+    player_query: Query<Entity, With<PlayerComponent>>,
 ) {
     if let Some(event) = event_read.read().last() {
+        // nothing from the previous attempt carries over, replays depend on it
+        if let Ok(player) = player_query.get_single() {
+            commands.entity(player).insert((
+                Speed::default(),
+                Acceleration::default(),
+                GravityCounter::default(),
+                CollisionSides::default(),
+                JumpLock::default(),
+                JumpTimers::default(),
+            ));
+        }
         // check for dir and number of levels
         println!("[PRELOADER] Loading run at {}...", &event.path);
-        match read_dir(&event.path.to_string()) {
+        match read_dir(&event.path) {
             Ok(ls) => {
                 print!(" Success!");
-                run_resource.len = ls.count() - 1;
-                // TODO: SET LATER TO NextLevel::Next(3)
-                run_resource.next = NextLevel::Next(3);
+                run_resource.len = level_numbers(ls).len();
+                // level 0 is loaded below, counting continues from it
+                run_resource.next = NextLevel::Next(0);
                 run_resource.path = event.path.to_owned();
             }
             Err(e) => {
                 println!(
                     " Failure. \n Couldn't read directory content due {}",
-                    e.to_string()
+                    e
                 )
             }
         }
@@ -115,7 +156,7 @@ pub fn handle_loadrunevent(
                 match f.read_to_string(&mut buf) {
                     Ok(_) => print!(" Success!"),
                     Err(e) => {
-                        print!(" Failure. \n Couldn't read data due {}", e.to_string());
+                        print!(" Failure. \n Couldn't read data due {}", e);
                         // TODO: Error message event
                     }
                 };
@@ -125,6 +166,48 @@ pub fn handle_loadrunevent(
                         None => "unknown",
                     }
                     .to_string();
+                // optional settings of the run
+                *physics_config = PhysicsConfig::default();
+                run_resource.music = None;
+                run_resource.par = vec![None; run_resource.len];
+                run_resource.medals.clear();
+                run_resource.level_medals = vec![Vec::new(); run_resource.len];
+                for line in buf.lines() {
+                    match line.split_ascii_whitespace().next() {
+                        Some("WALL_SLIDE") => physics_config.wall_slide = true,
+                        Some("WALL_JUMP") => physics_config.wall_jump = true,
+                        Some("COYOTE_TIME") => if let Some(Ok(ms)) = line.split_ascii_whitespace().nth(1).map(str::parse) {
+                            physics_config.coyote_time = ms;
+                        },
+                        Some("ABILITY") => match Ability::parse(&line.split_ascii_whitespace().collect::<Vec<&str>>()) {
+                            Some(ability) => physics_config.abilities.push(ability),
+                            None => println!("[PRELOADER] Unknown ability in '{}'", line),
+                        },
+                        Some("PAR") => {
+                            let segs: Vec<&str> = line.split_ascii_whitespace().collect();
+                            match (segs.get(1).and_then(|s| s.parse::<usize>().ok()), segs.get(2).and_then(|s| s.parse::<f32>().ok())) {
+                                (Some(level), Some(seconds)) if level < run_resource.len => run_resource.par[level] = Some(seconds),
+                                _ => println!("[PRELOADER] Invalid par time in '{}'", line),
+                            }
+                        }
+                        Some("MEDAL") => match MedalThreshold::parse(&line.split_ascii_whitespace().skip(1).collect::<Vec<&str>>()) {
+                            Some(threshold) => run_resource.medals.push(threshold),
+                            None => println!("[PRELOADER] Invalid medal in '{}'", line),
+                        },
+                        Some("LEVEL_MEDAL") => {
+                            let segs: Vec<&str> = line.split_ascii_whitespace().collect();
+                            match (segs.get(1).and_then(|s| s.parse::<usize>().ok()), MedalThreshold::parse(segs.get(2..).unwrap_or_default())) {
+                                (Some(level), Some(threshold)) if level < run_resource.len => run_resource.level_medals[level].push(threshold),
+                                _ => println!("[PRELOADER] Invalid level medal in '{}'", line),
+                            }
+                        }
+                        Some("MUSIC") => run_resource.music = line.split_ascii_whitespace().nth(1).map(str::to_string),
+                        Some("JUMP_BUFFER") => if let Some(Ok(ms)) = line.split_ascii_whitespace().nth(1).map(str::parse) {
+                            physics_config.jump_buffer = ms;
+                        },
+                        _ => {}
+                    }
+                }
             }
             Err(e) => {
                 println!(
@@ -141,32 +224,41 @@ pub fn handle_loadrunevent(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_loadlevelevent(
     mut event_read: EventReader<LoadLevelEvent>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    mut player_query: Query<(&mut Transform, &mut Speed), With<PlayerComponent>>,
+    mut player_query: Query<(Entity, &mut Transform, &mut Speed), With<PlayerComponent>>,
     level_query: Query<Entity, With<Level>>,
     mut score_resource: ResMut<Score>,
-    mut run_resource: ResMut<RunData>
+    mut run_resource: ResMut<RunData>,
+    physics_config: Res<PhysicsConfig>,
+    mut level_bounds: ResMut<LevelBounds>,
+    mut level_spawn: ResMut<LevelSpawn>,
 ) {
     if let Some(e) = event_read.read().last() {
         if let Ok(level_entity) = level_query.get_single() {
             println!("[LOADER] Despawning prior level");
             commands.entity(level_entity).despawn_recursive();
         }
-        if let Ok((mut transform, mut speed)) = player_query.get_single_mut() {
+        if let Ok((player_entity, mut transform, mut speed)) = player_query.get_single_mut() {
             println!("[LOADER] Starting loading of level {}", &e.path);
-            let coin_count = spawn_level(
+            let mut level_info = spawn_level(
                 e.path.clone(),
-                commands,
+                &mut commands,
                 &mut meshes,
                 &mut materials,
                 &mut transform,
                 &mut speed,
             );
-            score_resource.needed = coin_count;
+            // abilities of the run and of this level
+            level_info.abilities.extend(physics_config.abilities.iter());
+            grant_abilities(&mut commands.entity(player_entity), &level_info.abilities);
+            score_resource.needed = level_info.coins;
+            level_bounds.0 = level_info.camera_bounds();
+            level_spawn.0 = level_info.spawn;
             println!(
                 "Level score requirement (score.needed) set to {}",
                 score_resource.needed
@@ -189,9 +281,9 @@ pub fn handle_loadlevelevent(
 
 // ==== SYSTEMS ====
 
-pub fn test_loading(mut event_writer: EventWriter<LoadRunEvent>) {
+pub fn load_selected_run(mut event_writer: EventWriter<LoadRunEvent>, selected_run: Res<SelectedRun>) {
     event_writer.send(LoadRunEvent {
-        path: "./assets/levels/og4".to_string(),
+        path: selected_run.0.to_owned(),
     });
 }
 
@@ -219,6 +311,16 @@ pub enum LevelObject {
     Door(Vec2, Vec2),
     /// pos
     PlayerPos(Vec2),
+    /// pos, size, kind and name of the emitted signal
+    Switch(Vec2, Vec2, SwitchKind, String),
+    /// name of the signal, signal state in which the object is present and the object itself
+    Triggered(String, bool, Box<LevelObject>),
+    /// pos, size, impulse and cooldown (in ticks)
+    Spring(Vec2, Vec2, Vec2, u32),
+    /// ability unlocked for the level and its charges
+    Ability(Ability, u32),
+    /// area the camera is kept within
+    Bounds(Rect),
 }
 
 impl Default for LevelObject {
@@ -254,7 +356,7 @@ pub fn load_level_data(
         println!("Loading data into vector");
         for (n, l) in buff.lines().enumerate() {
             println!("{}", l);
-            match parser(l, n) {
+            match parser(l, n + 1) {
                 None => {}
                 Some(o) => ret.push(o),
             };
@@ -269,16 +371,55 @@ pub fn load_level_data(
 #[derive(Component)]
 pub struct Level;
 
+/// data gathered while spawning a level
+#[derive(Default)]
+pub struct LevelInfo {
+    /// number of coins in the level
+    pub coins: usize,
+    /// abilities unlocked by the level
+    pub abilities: Vec<(Ability, u32)>,
+    /// bounds declared by the level file
+    pub bounds: Option<Rect>,
+    /// area covered by the playfield and every obstacle
+    pub extent: Rect,
+    /// starting position of the player
+    pub spawn: Vec2,
+}
+
+impl LevelInfo {
+    /// declared bounds, or the extent when the level doesn't declare any
+    pub fn camera_bounds(&self) -> Rect {
+        self.bounds.unwrap_or(self.extent)
+    }
+}
+
+/// Starting position of the player in the current level, where they respawn
+#[derive(Resource, Default)]
+pub struct LevelSpawn(pub Vec2);
+
+/// Area the camera is kept within, set when a level gets loaded
+#[derive(Resource)]
+pub struct LevelBounds(pub Rect);
+
+impl Default for LevelBounds {
+    fn default() -> Self {
+        LevelBounds(Rect::new(0.0, 0.0, PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT))
+    }
+}
+
 /// spawn level object with objects from a given file
 pub fn spawn_level(
     path: String,
-    mut commands: Commands,
+    commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
     player_transform: &mut Transform,
     player_speed: &mut Speed,
-) -> usize {
-    let mut coin_count = 0;
+) -> LevelInfo {
+    let mut level_info = LevelInfo {
+        extent: Rect::new(0.0, 0.0, PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT),
+        ..default()
+    };
     commands
         .spawn((
             MaterialMesh2dBundle {
@@ -290,85 +431,185 @@ pub fn spawn_level(
             Level,
         ))
         .with_children(|parent| {
-            let data = load_level_data(path, &legacy_loading::parse_line);
+            let data = load_level_data(path.clone(), &legacy_loading::parse_line);
+            #[cfg(debug_assertions)]
+            for warning in validate_level(&data) {
+                println!("[VALIDATOR] Warning in {}: {}", path, warning);
+            }
             for o in data {
-                match o {
-                    LevelObject::Obstacle((pos, size)) => {
-                        parent.spawn((
-                            MaterialMesh2dBundle {
-                                mesh: Mesh2dHandle(meshes.add(Rectangle::new(size.x, size.y))),
-                                material: materials.add(Color::WHITE),
-                                transform: Transform::from_xyz(pos.x, pos.y, 0.0),
-                                ..default()
-                            },
-                            ObstacleComponent::default(),
-                            Size { 0: size },
-                        ));
-                    }
-                    LevelObject::Coin(pos) => {
-                        parent.spawn((
-                            MaterialMesh2dBundle {
-                                mesh: Mesh2dHandle(meshes.add(Circle::new(COIN_SIZE))),
-                                material: materials.add(Color::YELLOW),
-                                transform: Transform::from_xyz(pos.x, pos.y, 0.0),
-                                ..default()
-                            },
-                            CoinComponent,
-                            Size {
-                                0: Vec2::new(COIN_SIZE * 2.0, COIN_SIZE * 2.0),
-                            },
-                        ));
-                        coin_count += 1;
-                    }
-                    LevelObject::Door(pos, size) => {
-                        parent.spawn((
-                            MaterialMesh2dBundle {
-                                mesh: Mesh2dHandle(meshes.add(Rectangle::new(size.x, size.y))),
-                                material: materials.add(Color::ORANGE),
-                                transform: Transform::from_xyz(pos.x, pos.y, 0.0),
-                                ..default()
-                            },
-                            DoorComponent,
-                            Size { 0: size },
-                        ));
-                    }
-                    LevelObject::PlayerPos(pos) => {
-                        player_transform.translation.x = pos.x;
-                        player_transform.translation.y = pos.y;
-                        player_speed.0.x = 0.0;
-                        player_speed.0.y = 0.0;
-                    }
-                }
+                spawn_level_object(
+                    o,
+                    parent,
+                    meshes,
+                    materials,
+                    player_transform,
+                    player_speed,
+                    &mut level_info,
+                );
             }
         });
-    coin_count
+    level_info
 }
 
+/// spawn a single object as a child of the level, returns its entity if one was spawned
+pub fn spawn_level_object<'a>(
+    object: LevelObject,
+    parent: &'a mut ChildBuilder,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    player_transform: &mut Transform,
+    player_speed: &mut Speed,
+    level_info: &mut LevelInfo,
+) -> Option<EntityCommands<'a>> {
+    match object {
+        LevelObject::Obstacle((pos, size)) => {
+            level_info.extent = level_info.extent.union(Rect::from_center_size(pos, size));
+            Some(
+            parent
+                .spawn((
+                    MaterialMesh2dBundle {
+                        mesh: Mesh2dHandle(meshes.add(Rectangle::new(size.x, size.y))),
+                        material: materials.add(Color::WHITE),
+                        transform: Transform::from_xyz(pos.x, pos.y, 0.0),
+                        ..default()
+                    },
+                    ObstacleComponent,
+                    Size(size),
+                )),
+            )
+        }
+        LevelObject::Coin(pos) => {
+            level_info.coins += 1;
+            Some(
+                parent
+                    .spawn((
+                        MaterialMesh2dBundle {
+                            mesh: Mesh2dHandle(meshes.add(Circle::new(COIN_SIZE))),
+                            material: materials.add(Color::YELLOW),
+                            transform: Transform::from_xyz(pos.x, pos.y, 0.0),
+                            ..default()
+                        },
+                        CoinComponent,
+                        Size(Vec2::new(COIN_SIZE * 2.0, COIN_SIZE * 2.0)),
+                    )),
+            )
+        }
+        LevelObject::Door(pos, size) => Some(
+            parent
+                .spawn((
+                    MaterialMesh2dBundle {
+                        mesh: Mesh2dHandle(meshes.add(Rectangle::new(size.x, size.y))),
+                        material: materials.add(Color::ORANGE),
+                        transform: Transform::from_xyz(pos.x, pos.y, 0.0),
+                        ..default()
+                    },
+                    DoorComponent,
+                    Size(size),
+                )),
+        ),
+        LevelObject::PlayerPos(pos) => {
+            player_transform.translation.x = pos.x;
+            player_transform.translation.y = pos.y;
+            player_speed.0.x = 0.0;
+            player_speed.0.y = 0.0;
+            level_info.spawn = pos;
+            None
+        }
+        LevelObject::Switch(pos, size, kind, signal) => Some(
+            parent
+                .spawn((
+                    MaterialMesh2dBundle {
+                        mesh: Mesh2dHandle(meshes.add(Rectangle::new(size.x, size.y))),
+                        material: materials.add(SWITCH_OFF_COLOR),
+                        transform: Transform::from_xyz(pos.x, pos.y, 0.0),
+                        ..default()
+                    },
+                    SwitchComponent::new(kind, signal),
+                    Size(size),
+                )),
+        ),
+        LevelObject::Spring(pos, size, impulse, cooldown) => Some(
+            parent
+                .spawn((
+                    MaterialMesh2dBundle {
+                        mesh: Mesh2dHandle(meshes.add(Rectangle::new(size.x, size.y))),
+                        material: materials.add(SPRING_COLOR),
+                        transform: Transform::from_xyz(pos.x, pos.y, 0.0),
+                        ..default()
+                    },
+                    SpringComponent::new(impulse, cooldown),
+                    ObstacleComponent,
+                    Size(size),
+                )),
+        ),
+        LevelObject::Ability(ability, charges) => {
+            level_info.abilities.push((ability, charges));
+            None
+        }
+        LevelObject::Bounds(bounds) => {
+            level_info.bounds = Some(bounds);
+            None
+        }
+        LevelObject::Triggered(signal, present_when, inner) => {
+            let mut entity = spawn_level_object(
+                *inner,
+                parent,
+                meshes,
+                materials,
+                player_transform,
+                player_speed,
+                level_info,
+            )?;
+            // all signals start switched off
+            if present_when {
+                entity.insert((Visibility::Hidden, Inactive));
+            }
+            entity.insert(Triggerable { signal, present_when });
+            Some(entity)
+        }
+    }
+}
+
+/// Numbers of the levels in a run's directory, in order.<br>
+/// Levels are the files named by their number, anything else (info, leftovers) is skipped.
+pub fn level_numbers(entries: ReadDir) -> Vec<usize> {
+    let mut levels: Vec<usize> = entries.flatten()
+        .filter(|e| e.path().is_file())
+        .filter_map(|e| e.file_name().to_str()?.parse().ok())
+        .collect();
+    levels.sort();
+    levels
+}
+
+/// Short description of a run, used to list runs in the levels menu
 pub struct LevelData {
-    name: String,
-    author: u64,
-    amount: u8,
+    /// name of the run's directory
+    pub name: String,
+    pub author: String,
+    /// number of levels
+    pub amount: usize,
+    /// path used to load the run
+    pub path: String,
 }
 
 impl LevelData {
     pub fn read_dir(path: &DirEntry) -> Option<LevelData> {
-        let mut fs = File::open(format!("{}/info", path.path().display())).unwrap();
+        let mut fs = File::open(format!("{}/info", path.path().display())).ok()?;
         let mut buff = "".to_string();
-        fs.read_to_string(&mut buff).expect("FU2");
-        match buff.lines().into_iter().next() {
-            Some(o) => {
-                let mut oo = o.split_whitespace().into_iter();
-                oo.next().unwrap();
-                return Some(LevelData {
-                    amount: 0,
-                    author: 0,
-                    name: oo.next().expect("F").to_string(),
-                });
-            }
-            None => {
-                return None;
-            }
-        }
+        fs.read_to_string(&mut buff).ok()?;
+        let author = buff
+            .lines()
+            .find_map(|l| l.strip_prefix("AUTHOR "))
+            .unwrap_or("unknown")
+            .trim()
+            .to_string();
+        let amount = level_numbers(read_dir(path.path()).ok()?).len();
+        Some(LevelData {
+            name: path.file_name().to_string_lossy().to_string(),
+            author,
+            amount,
+            path: format!("{}{}", PATH_LEVELS, path.file_name().to_string_lossy()),
+        })
     }
 }
 
@@ -386,65 +627,137 @@ pub const PATH_LEVELS: &str = "./assets/levels/";
 
 pub fn get_levels_data() -> Vec<LevelData> {
     let mut n = Vec::new();
-    read_dir(PATH_LEVELS).expect("FU1").for_each(|x| {
-        match LevelData::read_dir(&x.as_ref().unwrap()) {
-            Some(o) => n.push(o),
-            None => {
-                println!("Failed to load {}", &x.unwrap().path().display())
-            }
-        };
-    });
+    match read_dir(PATH_LEVELS) {
+        Ok(ls) => ls.flatten().for_each(|x| {
+            match LevelData::read_dir(&x) {
+                Some(o) => n.push(o),
+                None => {
+                    println!("Failed to load {}", &x.path().display())
+                }
+            };
+        }),
+        Err(e) => println!("Cannot read levels directory {} due {}", PATH_LEVELS, e),
+    }
+    n.sort_by(|a, b| a.name.cmp(&b.name));
     n
 }
 
-mod legacy_loading {
+pub use legacy_loading::fix_aligment;
+
+pub(crate) mod legacy_loading {
     use super::{LevelObject, LEGACY_SCALE, PLAYER_SIZE};
+    use crate::{camera::PLAYFIELD_HEIGHT, game::{spring::SPRING_COOLDOWN, switch::SwitchKind, Ability}};
     use bevy::prelude::*;
 
+    /// object of a line, printing what's wrong with it if it can't be read
     pub fn parse_line(text: &str, number: usize) -> Option<LevelObject> {
-        let segs: Vec<&str> = text.split_ascii_whitespace().collect();
-        if segs.len() == 0 {
-            return None;
+        if let Some(keyword @ ("PLAYER_SIZE" | "COIN_SIZE")) = text.split_ascii_whitespace().next() {
+            println!("  > Identifier '{}' is redundant for new version", keyword);
         }
-        match segs[0] {
+        read_line(text).unwrap_or_else(|e| {
+            println!("  > Error at line number {}: {}", number, e);
+            None
+        })
+    }
+
+    /// pair of numbers starting at the given segment
+    fn vec2_at(segs: &[&str], n: usize) -> Option<Vec2> {
+        Some(Vec2::new(segs.get(n)?.parse().ok()?, segs.get(n + 1)?.parse().ok()?))
+    }
+
+    /// position and size of an object, the first four numbers after its identifier
+    fn rect_at(segs: &[&str]) -> Result<(Vec2, Vec2), String> {
+        let pos = vec2_at(segs, 1).ok_or("Missing or invalid position")?;
+        let size = vec2_at(segs, 3).ok_or("Missing or invalid size")?;
+        Ok((pos, size))
+    }
+
+    /// objects of a floor across the level with the spawn point on its left, and of some more lines
+    #[cfg(test)]
+    pub fn test_level(lines: &[&str]) -> Vec<LevelObject> {
+        ["BARRIER 0 460 640 20", "PLAYER_POS 0 430"].iter()
+            .chain(lines)
+            .filter_map(|l| read_line(l).unwrap())
+            .collect()
+    }
+
+    /// object of a line, none for lines without one (empty, redundant identifiers)
+    pub fn read_line(text: &str) -> Result<Option<LevelObject>, String> {
+        let segs: Vec<&str> = text.split_ascii_whitespace().collect();
+        let Some(&keyword) = segs.first() else {
+            return Ok(None);
+        };
+        let object = match keyword {
             "BARRIER" | "OBSTACLE" => {
-                let pos: Vec2 = Vec2::new(segs[1].parse().unwrap(), segs[2].parse().unwrap());
-                let size: Vec2 = Vec2::new(segs[3].parse().unwrap(), segs[4].parse().unwrap());
+                let (pos, size) = rect_at(&segs)?;
                 //println!("Obstacle -> pos:{}, size:{}",fix_aligment(pos, size),size);
-                Some(LevelObject::Obstacle((fix_aligment(pos, size), 2.0 * size)))
+                LevelObject::Obstacle((fix_aligment(pos, size), 2.0 * size))
             }
             "COIN" => {
-                let pos: Vec2 = Vec2::new(segs[1].parse().unwrap(), segs[2].parse().unwrap());
-                Some(LevelObject::Coin(fix_aligment(pos, Vec2::new(20.0, 20.0))))
+                let pos = vec2_at(&segs, 1).ok_or("Missing or invalid position")?;
+                LevelObject::Coin(fix_aligment(pos, Vec2::new(20.0, 20.0)))
             }
             "DOOR" => {
-                let pos: Vec2 = Vec2::new(segs[1].parse().unwrap(), segs[2].parse().unwrap());
-                let size: Vec2 = Vec2::new(segs[3].parse().unwrap(), segs[4].parse().unwrap());
-                Some(LevelObject::Door(fix_aligment(pos, size), size * 2.0))
+                let (pos, size) = rect_at(&segs)?;
+                LevelObject::Door(fix_aligment(pos, size), size * 2.0)
             }
             "PLAYER_POS" => {
-                let pos: Vec2 = Vec2::new(segs[1].parse().unwrap(), segs[2].parse().unwrap());
-                Some(LevelObject::PlayerPos(fix_aligment(
+                let pos = vec2_at(&segs, 1).ok_or("Missing or invalid position")?;
+                LevelObject::PlayerPos(fix_aligment(
                     pos,
                     Vec2::new(PLAYER_SIZE, PLAYER_SIZE),
-                )))
+                ))
             }
-            "PLAYER_SIZE" => {
-                println!("  > Identifier 'PLAYER_SIZE' is redundant for new version");
-                None
+            "LEVER" | "PLATE" => {
+                let (pos, size) = rect_at(&segs)?;
+                let kind = if keyword == "LEVER" {SwitchKind::Lever} else {SwitchKind::Plate};
+                let signal = segs.get(5).ok_or("Missing signal")?;
+                LevelObject::Switch(fix_aligment(pos, size), size * 2.0, kind, signal.to_string())
             }
-            "COIN_SIZE" => {
-                println!("  > Identifier 'COIN_SIZE' is redundant for new version");
-                None
+            "GATE" => {
+                // obstacle which disappears while its signal is on
+                let (pos, size) = rect_at(&segs)?;
+                let signal = segs.get(5).ok_or("Missing signal")?;
+                LevelObject::Triggered(
+                    signal.to_string(),
+                    false,
+                    Box::new(LevelObject::Obstacle((fix_aligment(pos, size), 2.0 * size))),
+                )
             }
-            _ => {
-                println!(
-                    "  > Error at line number {}: Wrong object identifier",
-                    number
-                );
-                None
+            "SPRING" => {
+                // SPRING x y w h impulse_x impulse_y [cooldown], impulse uses game units (OY pointing up)
+                let (pos, size) = rect_at(&segs)?;
+                let impulse = vec2_at(&segs, 5).ok_or("Missing or invalid spring impulse")?;
+                let cooldown: u32 = match segs.get(7) {
+                    Some(c) => c.parse().unwrap_or(SPRING_COOLDOWN),
+                    None => SPRING_COOLDOWN,
+                };
+                LevelObject::Spring(fix_aligment(pos, size), size * 2.0, impulse, cooldown)
             }
-        }
+            "BOUNDS" => {
+                // BOUNDS x y w h - area the camera may show
+                let (pos, size) = rect_at(&segs)?;
+                LevelObject::Bounds(Rect::from_center_size(fix_aligment(pos, size), size * 2.0))
+            }
+            "ABILITY" => {
+                let (ability, charges) = Ability::parse(&segs).ok_or("Unknown ability")?;
+                LevelObject::Ability(ability, charges)
+            }
+            "ON" | "OFF" => {
+                // ON/OFF <signal> <object> - object present only while the signal is on/off
+                if segs.len() < 3 {
+                    return Err("Missing signal or object".to_string());
+                }
+                let inner = read_line(&segs[2..].join(" "))?.ok_or("Missing object")?;
+                LevelObject::Triggered(segs[1].to_string(), keyword == "ON", Box::new(inner))
+            }
+            // sizes of the old version, fixed now
+            "PLAYER_SIZE" | "COIN_SIZE" => return Ok(None),
+            // comments, e.g. "// EXIT" or a commented out object
+            _ if keyword.starts_with("//") => return Ok(None),
+            _ => return Err("Wrong object identifier".to_string()),
+        };
+        Ok(Some(object))
     }
 
     /// fixes alignment issues caused by centering of pos vec by bevy<br>
@@ -452,7 +765,7 @@ mod legacy_loading {
     pub fn fix_aligment(pos: Vec2, size: Vec2) -> Vec2 {
         Vec2::new(
             LEGACY_SCALE * (pos.x + size.x / 2.0),
-            -LEGACY_SCALE * (pos.y + size.y / 2.0) + 960.0,
+            -LEGACY_SCALE * (pos.y + size.y / 2.0) + PLAYFIELD_HEIGHT,
         )
     }
 }
@@ -4,4 +4,8 @@ pub fn reduction(v: f32, p: f32, b: f32) -> f32 {
     }else{
         v*p
     }
-}
\ No newline at end of file
+}
+/// converts milliseconds into a number of fixed ticks lasting `tick` seconds
+pub fn ms_to_ticks(ms: u32, tick: f32) -> u32 {
+    (ms as f32 / 1000.0 / tick).round() as u32
+}
@@ -2,4 +2,19 @@ pub mod math;
 pub use math as mymath;
 
 pub mod loader;
-pub use loader::*;
\ No newline at end of file
+pub use loader::*;
+
+pub mod bindings;
+pub use bindings::*;
+
+pub mod settings;
+pub use settings::*;
+
+pub mod save;
+pub use save::*;
+
+pub mod validate;
+pub use validate::*;
+
+pub mod leaderboard;
+pub use leaderboard::*;
@@ -0,0 +1,217 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{Read, Write},
+};
+
+use bevy::prelude::*;
+
+use crate::{editor::Playtest, game::{LevelCompleteEvent, Medal}};
+
+pub const PATH_SAVE: &str = "./config/save";
+
+// ==== PLUGIN ====
+
+/// Keeps the best results of every run and level, saved after each finished level
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //.
+        .insert_resource(SaveData::load(PATH_SAVE))
+        .add_systems(Update, record_completed_levels)
+        //.
+        ;
+    }
+}
+
+// ==== RESOURCE ====
+
+/// Best results of a run or of a single level
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Record {
+    /// time (in seconds) of the fastest finish
+    pub best_time: Option<f32>,
+    pub max_coins: usize,
+    pub completed: bool,
+    /// best medal earned
+    pub medal: Option<Medal>,
+}
+
+impl Record {
+    /// add a finished attempt, returns whether its time is a new best
+    pub fn update(&mut self, time: f32, coins: usize, medal: Option<Medal>) -> bool {
+        self.completed = true;
+        self.max_coins = self.max_coins.max(coins);
+        self.medal = self.medal.max(medal);
+        let new_best = match self.best_time {
+            Some(best) => time < best,
+            None => true,
+        };
+        if new_best {
+            self.best_time = Some(time);
+        }
+        new_best
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RunSave {
+    /// results of the whole run, completed once its last level was finished
+    pub run: Record,
+    /// results of each level, by its number
+    pub levels: BTreeMap<usize, Record>,
+    /// run time at the end of each level in the best run
+    pub splits: Vec<f32>,
+}
+
+impl RunSave {
+    pub fn completed_levels(&self) -> usize {
+        self.levels.values().filter(|l| l.completed).count()
+    }
+}
+
+/// Save file, runs are stored by the path they're loaded from
+#[derive(Resource, Clone, PartialEq, Debug, Default)]
+pub struct SaveData {
+    pub runs: HashMap<String, RunSave>,
+}
+
+impl SaveData {
+    /// load the save from a file, one record per line under the run it belongs to:
+    /// ```text
+    /// RUN <path>
+    /// BEST <time|-> <coins> <ON|OFF> [medal]
+    /// LEVEL <number> <time|-> <coins> <ON|OFF> [medal]
+    /// SPLITS <time> <time> ...
+    /// ```
+    pub fn load(path: &str) -> SaveData {
+        let mut save = SaveData::default();
+        let mut buff = String::new();
+        match File::open(path) {
+            Ok(mut f) => {
+                if let Err(e) = f.read_to_string(&mut buff) {
+                    println!("[SAVE] Couldn't read {} due {}", path, e);
+                }
+            }
+            Err(_) => {
+                println!("[SAVE] No save file at {}, starting fresh", path);
+                return save;
+            }
+        }
+        let mut current: Option<String> = None;
+        for (n, line) in buff.lines().enumerate() {
+            let (keyword, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let segs: Vec<&str> = rest.split_ascii_whitespace().collect();
+            if keyword == "RUN" {
+                // the path is the rest of the line, it may contain spaces
+                save.runs.entry(rest.to_string()).or_default();
+                current = Some(rest.to_string());
+                continue;
+            }
+            match (keyword, current.as_ref().and_then(|path| save.runs.get_mut(path))) {
+                ("", _) => {}
+                ("BEST", Some(run)) => match parse_record(&segs) {
+                    Some(record) => run.run = record,
+                    None => println!("[SAVE] Error at line number {}: Invalid record", n),
+                },
+                ("LEVEL", Some(run)) => match (segs.first().and_then(|s| s.parse().ok()), parse_record(segs.get(1..).unwrap_or_default())) {
+                    (Some(level), Some(record)) => {
+                        run.levels.insert(level, record);
+                    }
+                    _ => println!("[SAVE] Error at line number {}: Invalid level record", n),
+                },
+                ("SPLITS", Some(run)) => match segs.iter().map(|s| s.parse()).collect() {
+                    Ok(splits) => run.splits = splits,
+                    Err(_) => println!("[SAVE] Error at line number {}: Invalid splits", n),
+                },
+                ("BEST" | "LEVEL" | "SPLITS", None) => println!("[SAVE] Error at line number {}: Record outside of a run", n),
+                _ => println!("[SAVE] Error at line number {}: Unknown keyword {}", n, keyword),
+            }
+        }
+        println!("[SAVE] Loaded {} runs from {}", save.runs.len(), path);
+        save
+    }
+
+    /// write to a temporary file first and move it over the save,
+    /// so a crash mid-write can't corrupt it
+    pub fn save(&self, path: &str) {
+        let mut buff = String::new();
+        let mut paths: Vec<&String> = self.runs.keys().collect();
+        paths.sort();
+        for run_path in paths {
+            let run = &self.runs[run_path];
+            buff += &format!("RUN {}\nBEST {}\n", run_path, format_record(&run.run));
+            for (level, record) in run.levels.iter() {
+                buff += &format!("LEVEL {} {}\n", level, format_record(record));
+            }
+            if !run.splits.is_empty() {
+                let splits: Vec<String> = run.splits.iter().map(f32::to_string).collect();
+                buff += &format!("SPLITS {}\n", splits.join(" "));
+            }
+        }
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let temp = format!("{}.tmp", path);
+        match File::create(&temp)
+            .and_then(|mut f| f.write_all(buff.as_bytes()).and_then(|_| f.sync_all()))
+            .and_then(|_| fs::rename(&temp, path))
+        {
+            Ok(_) => println!("[SAVE] Saved to {}", path),
+            Err(e) => println!("[SAVE] Couldn't save to {} due {}", path, e),
+        }
+    }
+}
+
+fn parse_record(segs: &[&str]) -> Option<Record> {
+    let best_time = match *segs.first()? {
+        "-" => None,
+        time => Some(time.parse().ok()?),
+    };
+    Some(Record {
+        best_time,
+        max_coins: segs.get(1)?.parse().ok()?,
+        completed: *segs.get(2)? == "ON",
+        medal: segs.get(3).and_then(|s| Medal::from_name(s)),
+    })
+}
+
+fn format_record(record: &Record) -> String {
+    let mut buff = format!(
+        "{} {} {}",
+        record.best_time.map_or("-".to_string(), |t| t.to_string()),
+        record.max_coins,
+        if record.completed {"ON"} else {"OFF"},
+    );
+    if let Some(medal) = record.medal {
+        buff += &format!(" {}", medal.name());
+    }
+    buff
+}
+
+// ==== SYSTEMS ====
+
+pub fn record_completed_levels(
+    mut event_reader: EventReader<LevelCompleteEvent>,
+    mut save: ResMut<SaveData>,
+    playtest: Option<Res<Playtest>>,
+) {
+    let mut changed = false;
+    // levels played from the editor aren't recorded
+    for event in event_reader.read().filter(|_| playtest.is_none()) {
+        let run = save.runs.entry(event.run.clone()).or_default();
+        if run.levels.entry(event.level).or_default().update(event.level_time, event.level_coins, event.level_medal) {
+            println!("[SAVE] New best time on level {}", event.level);
+        }
+        if event.last && run.run.update(event.run_time, event.run_coins, event.run_medal) {
+            println!("[SAVE] New best time on {}", event.run);
+            run.splits = event.splits.clone();
+        }
+        changed = true;
+    }
+    if changed {
+        save.save(PATH_SAVE);
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+};
+
+use bevy::{
+    audio::Volume,
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode},
+};
+
+use crate::WINDOW_SCALE;
+
+pub const PATH_SETTINGS: &str = "./config/settings";
+/// window scales the settings menu cycles through
+pub const WINDOW_SCALES: [f32; 4] = [1.0, 1.5, 2.0, 3.0];
+
+// ==== PLUGIN ====
+
+/// Keeps the window and global volume in sync with Settings.<br>
+/// Settings itself is loaded in `main`, as the window is created from it.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //.
+        .add_systems(Update, apply_settings)
+        //.
+        ;
+    }
+}
+
+// ==== RESOURCE ====
+
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct Settings {
+    /// 0.0 - 1.0, scales every sound
+    pub master_volume: f32,
+    /// 0.0 - 1.0, scales sound effects
+    pub sfx_volume: f32,
+    /// 0.0 - 1.0, scales menu sounds
+    pub ui_volume: f32,
+    /// 0.0 - 1.0, scales music
+    pub music_volume: f32,
+    /// window size relative to the original 640x480
+    pub window_scale: f32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub show_timer: bool,
+    pub show_abilities: bool,
+    pub screen_shake: bool,
+    /// squash and dust on hard landings
+    pub impact_effects: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            ui_volume: 1.0,
+            music_volume: 0.7,
+            window_scale: WINDOW_SCALE,
+            fullscreen: false,
+            vsync: true,
+            show_timer: true,
+            show_abilities: true,
+            screen_shake: true,
+            impact_effects: true,
+        }
+    }
+}
+
+impl Settings {
+    pub fn window_size(&self) -> Vec2 {
+        Vec2::new(640.0, 480.0) * self.window_scale
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {WindowMode::BorderlessFullscreen} else {WindowMode::Windowed}
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {PresentMode::AutoVsync} else {PresentMode::AutoNoVsync}
+    }
+
+    /// load settings from a file, options missing from it keep their defaults
+    pub fn load(path: &str) -> Settings {
+        let mut settings = Settings::default();
+        let mut buff = String::new();
+        match File::open(path) {
+            Ok(mut f) => {
+                if let Err(e) = f.read_to_string(&mut buff) {
+                    println!("[SETTINGS] Couldn't read {} due {}", path, e);
+                }
+            }
+            Err(_) => {
+                println!("[SETTINGS] No settings file at {}, using defaults", path);
+                return settings;
+            }
+        }
+        for (n, line) in buff.lines().enumerate() {
+            let segs: Vec<&str> = line.split_ascii_whitespace().collect();
+            if segs.len() < 2 {
+                continue;
+            }
+            let number = segs[1].parse::<f32>().ok();
+            let flag = segs[1] == "ON";
+            match segs[0] {
+                "MASTER_VOLUME" => settings.master_volume = number.unwrap_or(1.0).clamp(0.0, 1.0),
+                "SFX_VOLUME" => settings.sfx_volume = number.unwrap_or(1.0).clamp(0.0, 1.0),
+                "UI_VOLUME" => settings.ui_volume = number.unwrap_or(1.0).clamp(0.0, 1.0),
+                "MUSIC_VOLUME" => settings.music_volume = number.unwrap_or(1.0).clamp(0.0, 1.0),
+                "WINDOW_SCALE" => settings.window_scale = number.unwrap_or(WINDOW_SCALE).clamp(0.5, 4.0),
+                "FULLSCREEN" => settings.fullscreen = flag,
+                "VSYNC" => settings.vsync = flag,
+                "SHOW_TIMER" => settings.show_timer = flag,
+                "SHOW_ABILITIES" => settings.show_abilities = flag,
+                "SCREEN_SHAKE" => settings.screen_shake = flag,
+                "IMPACT_EFFECTS" => settings.impact_effects = flag,
+                _ => println!("[SETTINGS] Error at line number {}: Unknown option {}", n, segs[0]),
+            }
+        }
+        println!("[SETTINGS] Loaded settings from {}", path);
+        settings
+    }
+
+    pub fn save(&self, path: &str) {
+        let flag = |b: bool| if b {"ON"} else {"OFF"};
+        let buff = format!(
+            "MASTER_VOLUME {}\nSFX_VOLUME {}\nUI_VOLUME {}\nMUSIC_VOLUME {}\nWINDOW_SCALE {}\nFULLSCREEN {}\nVSYNC {}\nSHOW_TIMER {}\nSHOW_ABILITIES {}\nSCREEN_SHAKE {}\nIMPACT_EFFECTS {}\n",
+            self.master_volume,
+            self.sfx_volume,
+            self.ui_volume,
+            self.music_volume,
+            self.window_scale,
+            flag(self.fullscreen),
+            flag(self.vsync),
+            flag(self.show_timer),
+            flag(self.show_abilities),
+            flag(self.screen_shake),
+            flag(self.impact_effects),
+        );
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        match File::create(path).and_then(|mut f| f.write_all(buff.as_bytes())) {
+            Ok(_) => println!("[SETTINGS] Saved settings to {}", path),
+            Err(e) => println!("[SETTINGS] Couldn't save settings to {} due {}", path, e),
+        }
+    }
+}
+
+// ==== OPTIONS ====
+
+/// Option shown in the settings menu
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SettingKind {
+    MasterVolume,
+    SfxVolume,
+    UiVolume,
+    MusicVolume,
+    WindowScale,
+    Fullscreen,
+    Vsync,
+    ShowTimer,
+    ShowAbilities,
+    ScreenShake,
+    ImpactEffects,
+}
+
+impl SettingKind {
+    pub const ALL: [SettingKind; 11] = [
+        SettingKind::MasterVolume,
+        SettingKind::SfxVolume,
+        SettingKind::UiVolume,
+        SettingKind::MusicVolume,
+        SettingKind::WindowScale,
+        SettingKind::Fullscreen,
+        SettingKind::Vsync,
+        SettingKind::ShowTimer,
+        SettingKind::ShowAbilities,
+        SettingKind::ScreenShake,
+        SettingKind::ImpactEffects,
+    ];
+
+    /// name used in menu files
+    pub fn from_name(name: &str) -> Option<SettingKind> {
+        match name {
+            "MASTER_VOLUME" => Some(SettingKind::MasterVolume),
+            "SFX_VOLUME" => Some(SettingKind::SfxVolume),
+            "UI_VOLUME" => Some(SettingKind::UiVolume),
+            "MUSIC_VOLUME" => Some(SettingKind::MusicVolume),
+            "WINDOW_SCALE" => Some(SettingKind::WindowScale),
+            "FULLSCREEN" => Some(SettingKind::Fullscreen),
+            "VSYNC" => Some(SettingKind::Vsync),
+            "SHOW_TIMER" => Some(SettingKind::ShowTimer),
+            "SHOW_ABILITIES" => Some(SettingKind::ShowAbilities),
+            "SCREEN_SHAKE" => Some(SettingKind::ScreenShake),
+            "IMPACT_EFFECTS" => Some(SettingKind::ImpactEffects),
+            _ => None,
+        }
+    }
+
+    /// text shown on the option's button
+    pub fn label(&self, settings: &Settings) -> String {
+        let flag = |b: bool| if b {"On"} else {"Off"};
+        match self {
+            SettingKind::MasterVolume => format!("Volume: {:.0}%", settings.master_volume * 100.0),
+            SettingKind::SfxVolume => format!("Effects: {:.0}%", settings.sfx_volume * 100.0),
+            SettingKind::UiVolume => format!("Menu sounds: {:.0}%", settings.ui_volume * 100.0),
+            SettingKind::MusicVolume => format!("Music: {:.0}%", settings.music_volume * 100.0),
+            SettingKind::WindowScale => format!("Window: {}x", settings.window_scale),
+            SettingKind::Fullscreen => format!("Fullscreen: {}", flag(settings.fullscreen)),
+            SettingKind::Vsync => format!("Vsync: {}", flag(settings.vsync)),
+            SettingKind::ShowTimer => format!("Timer: {}", flag(settings.show_timer)),
+            SettingKind::ShowAbilities => format!("Abilities: {}", flag(settings.show_abilities)),
+            SettingKind::ScreenShake => format!("Screen shake: {}", flag(settings.screen_shake)),
+            SettingKind::ImpactEffects => format!("Impact effects: {}", flag(settings.impact_effects)),
+        }
+    }
+
+    /// switch to the next value of the option, volumes go up by 10% and wrap around
+    pub fn cycle(&self, settings: &mut Settings) {
+        let step = |v: f32| if v >= 0.95 {0.0} else {((v * 10.0).round() + 1.0) / 10.0};
+        match self {
+            SettingKind::MasterVolume => settings.master_volume = step(settings.master_volume),
+            SettingKind::SfxVolume => settings.sfx_volume = step(settings.sfx_volume),
+            SettingKind::UiVolume => settings.ui_volume = step(settings.ui_volume),
+            SettingKind::MusicVolume => settings.music_volume = step(settings.music_volume),
+            SettingKind::WindowScale => {
+                let i = WINDOW_SCALES.iter().position(|s| *s == settings.window_scale).unwrap_or(0);
+                settings.window_scale = WINDOW_SCALES[(i + 1) % WINDOW_SCALES.len()];
+            }
+            SettingKind::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            SettingKind::Vsync => settings.vsync = !settings.vsync,
+            SettingKind::ShowTimer => settings.show_timer = !settings.show_timer,
+            SettingKind::ShowAbilities => settings.show_abilities = !settings.show_abilities,
+            SettingKind::ScreenShake => settings.screen_shake = !settings.screen_shake,
+            SettingKind::ImpactEffects => settings.impact_effects = !settings.impact_effects,
+        }
+    }
+}
+
+// ==== SYSTEMS ====
+
+pub fn apply_settings(
+    settings: Res<Settings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut global_volume: ResMut<GlobalVolume>,
+) {
+    if !settings.is_changed() {return;}
+    if let Ok(mut window) = window_query.get_single_mut() {
+        let size = settings.window_size();
+        window.resolution.set(size.x, size.y);
+        window.mode = settings.window_mode();
+        window.present_mode = settings.present_mode();
+    }
+    // only affects sounds started from now on
+    global_volume.volume = Volume::new(settings.master_volume);
+}
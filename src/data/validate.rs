@@ -0,0 +1,266 @@
+use std::{
+    fmt,
+    fs::{read_dir, File},
+    io::Read,
+};
+
+use bevy::prelude::*;
+
+use crate::camera::{PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
+
+use super::{legacy_loading, level_numbers, LevelObject, COIN_SIZE, LEGACY_SCALE, PLAYER_SIZE};
+
+/// how far (in px) walls may be outside of the bounds
+pub const BOUNDS_MARGIN: f32 = 4.0;
+
+// ==== WARNINGS ====
+
+/// Problem found in a level, positions are centers in level file units (the legacy 640x480 space)
+#[derive(Clone, PartialEq, Debug)]
+pub enum LevelWarning {
+    MissingSpawn,
+    /// number of spawn points
+    DuplicateSpawn(usize),
+    MissingDoor,
+    /// name of the object and its position
+    OutOfBounds(&'static str, Vec2),
+    CoinInObstacle(Vec2),
+    SpawnInObstacle(Vec2),
+    /// names and positions of two objects which shouldn't overlap
+    Overlap(&'static str, Vec2, &'static str, Vec2),
+    /// line number and what's wrong with the line, the game skips it
+    InvalidLine(usize, String),
+}
+
+impl fmt::Display for LevelWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelWarning::MissingSpawn => write!(f, "No spawn point (PLAYER_POS)"),
+            LevelWarning::DuplicateSpawn(n) => write!(f, "{} spawn points, the last one is used", n),
+            LevelWarning::MissingDoor => write!(f, "No door, the level can't be finished"),
+            LevelWarning::OutOfBounds(name, pos) => write!(f, "The {} at {} is out of bounds", name, format_pos(*pos)),
+            LevelWarning::CoinInObstacle(pos) => write!(f, "The coin at {} is inside an obstacle", format_pos(*pos)),
+            LevelWarning::SpawnInObstacle(pos) => write!(f, "The spawn point at {} overlaps an obstacle", format_pos(*pos)),
+            LevelWarning::Overlap(a, pa, b, pb) => {
+                write!(f, "The {} at {} overlaps the {} at {}", a, format_pos(*pa), b, format_pos(*pb))
+            }
+            LevelWarning::InvalidLine(n, e) => write!(f, "Line {} is skipped: {}", n, e),
+        }
+    }
+}
+
+pub fn format_pos(pos: Vec2) -> String {
+    format!("({:.1}, {:.1})", pos.x, pos.y)
+}
+
+/// game coords to level file units
+pub fn legacy_pos(pos: Vec2) -> Vec2 {
+    Vec2::new(pos.x / LEGACY_SCALE, (PLAYFIELD_HEIGHT - pos.y) / LEGACY_SCALE)
+}
+
+// ==== VALIDATION ====
+
+/// Object of a level with the area it covers in game
+struct Placed {
+    name: &'static str,
+    rect: Rect,
+    /// blocks the player
+    solid: bool,
+    /// present only for some state of a signal
+    triggered: bool,
+}
+
+fn place(object: &LevelObject, triggered: bool, placed: &mut Vec<Placed>, bounds: &mut Option<Rect>) {
+    let mut add = |name, pos: Vec2, size: Vec2, solid| placed.push(Placed {
+        name,
+        rect: Rect::from_center_size(pos, size),
+        solid,
+        triggered,
+    });
+    match object {
+        LevelObject::Obstacle((pos, size)) => add("obstacle", *pos, *size, true),
+        LevelObject::Coin(pos) => add("coin", *pos, Vec2::splat(COIN_SIZE * 2.0), false),
+        LevelObject::Door(pos, size) => add("door", *pos, *size, false),
+        LevelObject::PlayerPos(pos) => add("spawn point", *pos, Vec2::splat(PLAYER_SIZE), false),
+        LevelObject::Switch(pos, size, _, _) => add("switch", *pos, *size, false),
+        LevelObject::Spring(pos, size, _, _) => add("spring", *pos, *size, true),
+        LevelObject::Triggered(_, _, inner) => place(inner, true, placed, bounds),
+        LevelObject::Bounds(rect) => *bounds = Some(*rect),
+        LevelObject::Ability(_, _) => {}
+    }
+}
+
+fn overlap(a: Rect, b: Rect) -> bool {
+    !a.intersect(b).is_empty()
+}
+
+/// check a level for mistakes which make it unplayable or look like typos<br>
+/// walls may stick out of the bounds, everything else has to be inside
+pub fn validate_level(objects: &[LevelObject]) -> Vec<LevelWarning> {
+    let mut warnings = Vec::new();
+    let mut placed = Vec::new();
+    let mut bounds = None;
+    for object in objects {
+        place(object, false, &mut placed, &mut bounds);
+    }
+    let bounds = bounds.unwrap_or(Rect::new(0.0, 0.0, PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT));
+    let spawns = placed.iter().filter(|p| p.name == "spawn point").count();
+    match spawns {
+        0 => warnings.push(LevelWarning::MissingSpawn),
+        1 => {}
+        n => warnings.push(LevelWarning::DuplicateSpawn(n)),
+    }
+    if !placed.iter().any(|p| p.name == "door") {
+        warnings.push(LevelWarning::MissingDoor);
+    }
+    for p in placed.iter() {
+        let inside = match (p.solid, p.name) {
+            // walls around the bounds only touch them
+            (true, _) => overlap(p.rect, Rect::from_center_size(bounds.center(), bounds.size() + 2.0 * BOUNDS_MARGIN)),
+            (_, "spawn point") => bounds.contains(p.rect.center()),
+            _ => bounds.contains(p.rect.min) && bounds.contains(p.rect.max),
+        };
+        if !inside {
+            warnings.push(LevelWarning::OutOfBounds(p.name, legacy_pos(p.rect.center())));
+        }
+    }
+    // obstacles which come and go may cover things on purpose
+    let obstacles: Vec<&Placed> = placed.iter().filter(|p| p.solid && !p.triggered).collect();
+    let others: Vec<&Placed> = placed.iter().filter(|p| !p.solid).collect();
+    for p in others.iter() {
+        // the player gets pushed out of shallow overlaps, so only its center counts
+        let hit = |o: &&&Placed| if p.name == "spawn point" {o.rect.contains(p.rect.center())} else {overlap(p.rect, o.rect)};
+        let Some(obstacle) = obstacles.iter().find(hit) else {continue};
+        let pos = legacy_pos(p.rect.center());
+        warnings.push(match p.name {
+            "coin" => LevelWarning::CoinInObstacle(pos),
+            "spawn point" => LevelWarning::SpawnInObstacle(pos),
+            name => LevelWarning::Overlap(name, pos, obstacle.name, legacy_pos(obstacle.rect.center())),
+        });
+    }
+    for (i, a) in others.iter().enumerate() {
+        for b in others[i + 1..].iter().filter(|b| overlap(a.rect, b.rect)) {
+            warnings.push(LevelWarning::Overlap(a.name, legacy_pos(a.rect.center()), b.name, legacy_pos(b.rect.center())));
+        }
+    }
+    warnings
+}
+
+// ==== LINT ====
+
+/// objects of a level file, without spawning anything, and the lines which couldn't be read
+pub fn read_level_objects(path: &str) -> Option<(Vec<LevelObject>, Vec<LevelWarning>)> {
+    let mut buff = String::new();
+    File::open(path).and_then(|mut f| f.read_to_string(&mut buff)).ok()?;
+    let mut objects = Vec::new();
+    let mut warnings = Vec::new();
+    for (n, line) in buff.lines().enumerate() {
+        match legacy_loading::read_line(line) {
+            Ok(object) => objects.extend(object),
+            Err(e) => warnings.push(LevelWarning::InvalidLine(n + 1, e)),
+        }
+    }
+    Some((objects, warnings))
+}
+
+/// paths of every level of every run in a directory, in order
+pub fn level_paths(path: &str) -> Result<Vec<String>, String> {
+    let mut runs: Vec<_> = read_dir(path)
+        .map_err(|e| format!("Cannot read levels directory {} due {}", path, e))?
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| e.path())
+        .collect();
+    runs.sort();
+    let mut paths = Vec::new();
+    for run in runs {
+        let levels = read_dir(&run).map_or(Vec::new(), level_numbers);
+        paths.extend(levels.iter().map(|level| format!("{}/{}", run.display(), level)));
+    }
+    Ok(paths)
+}
+
+/// Validate every level of every run in a directory, returns the number of warnings
+pub fn lint_levels(path: &str) -> usize {
+    let paths = match level_paths(path) {
+        Ok(paths) => paths,
+        Err(e) => {
+            println!("[LINT] {}", e);
+            return 1;
+        }
+    };
+    let mut total = 0;
+    for level_path in paths {
+        let Some((objects, mut warnings)) = read_level_objects(&level_path) else {
+            println!("[LINT] {}: Couldn't read the level", level_path);
+            total += 1;
+            continue;
+        };
+        warnings.extend(validate_level(&objects));
+        for warning in warnings.iter() {
+            println!("[LINT] {}: {}", level_path, warning);
+        }
+        total += warnings.len();
+    }
+    println!("[LINT] {} warnings", total);
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use legacy_loading::test_level;
+
+    const DOOR: &str = "DOOR 600 380 40 80";
+
+    #[test]
+    fn valid_level_has_no_warnings() {
+        assert_eq!(validate_level(&test_level(&[DOOR, "COIN 300 400"])), vec![]);
+    }
+
+    #[test]
+    fn spawn_points_are_counted() {
+        let objects: Vec<LevelObject> = test_level(&[DOOR]).into_iter()
+            .filter(|o| !matches!(o, LevelObject::PlayerPos(_)))
+            .collect();
+        assert_eq!(validate_level(&objects), vec![LevelWarning::MissingSpawn]);
+        assert_eq!(validate_level(&test_level(&[DOOR, "PLAYER_POS 100 430"])), vec![LevelWarning::DuplicateSpawn(2)]);
+    }
+
+    #[test]
+    fn missing_door_is_reported() {
+        assert_eq!(validate_level(&test_level(&[])), vec![LevelWarning::MissingDoor]);
+    }
+
+    #[test]
+    fn coin_in_obstacle_is_reported() {
+        let warnings = validate_level(&test_level(&[DOOR, "COIN 300 455"]));
+        assert_eq!(warnings, vec![LevelWarning::CoinInObstacle(Vec2::new(310.0, 465.0))]);
+        // unless the obstacle comes and goes
+        assert_eq!(validate_level(&test_level(&[DOOR, "COIN 310 310", "GATE 300 300 50 50 s"])), vec![]);
+    }
+
+    #[test]
+    fn objects_out_of_bounds_are_reported() {
+        let warnings = validate_level(&test_level(&[DOOR, "COIN 700 100"]));
+        assert_eq!(warnings, vec![LevelWarning::OutOfBounds("coin", Vec2::new(710.0, 110.0))]);
+        // walls just outside the bounds are fine, far away ones aren't
+        assert_eq!(validate_level(&test_level(&[DOOR, "BARRIER -10 0 10 480"])), vec![]);
+        let warnings = validate_level(&test_level(&[DOOR, "BARRIER -100 0 10 480"]));
+        assert_eq!(warnings, vec![LevelWarning::OutOfBounds("obstacle", Vec2::new(-95.0, 240.0))]);
+    }
+
+    #[test]
+    fn unreadable_lines_are_explained() {
+        assert!(matches!(legacy_loading::read_line("// EXIT"), Ok(None)));
+        assert!(matches!(legacy_loading::read_line("PLAYER_SIZE 30 30"), Ok(None)));
+        assert!(legacy_loading::read_line("BARRIER 0 460 640").is_err());
+        assert!(legacy_loading::read_line("COIN 1 x").is_err());
+        assert!(legacy_loading::read_line("GATE 0 0 10 10").is_err());
+        assert!(legacy_loading::read_line("SPRING 0 0 10 10 5").is_err());
+        assert!(legacy_loading::read_line("ON s").is_err());
+        assert!(legacy_loading::read_line("TELEPORTER 0 0").is_err());
+        assert!(legacy_loading::parse_line("DOOR 600", 3).is_none());
+    }
+}
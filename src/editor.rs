@@ -0,0 +1,763 @@
+use std::{
+    fs::{self, read_dir, File},
+    io::{Read, Write},
+};
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    camera::{CameraFocus, MainCamera, PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH},
+    data::{fix_aligment, level_numbers, InputAction, InputBindings, Leaderboard, SelectedRun, COIN_SIZE, LEGACY_SCALE, PLAYER_SIZE},
+    game::LevelCompleteEvent,
+    menu::styles::get_normal_text_style,
+    AppState, HudState,
+};
+
+// ==== CONSTANTS ====
+/// temporary run the edited level is played from
+pub const PATH_PLAYTEST: &str = "./config/playtest";
+/// step of the grid, in level file units (the legacy 640x480 space)
+pub const EDITOR_GRID: f32 = 10.0;
+/// speed (in px per second) of moving the view with the arrow keys
+pub const EDITOR_PAN_SPEED: f32 = 600.0;
+/// changes which can be undone
+pub const EDITOR_HISTORY: usize = 100;
+pub const EDITOR_SELECTED_COLOR: Color = Color::GREEN;
+pub const EDITOR_SPAWN_COLOR: Color = Color::rgb(0.3, 0.8, 1.0);
+pub const EDITOR_GRID_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.06);
+const EDITOR_HELP: &str = "1-4 tool  LMB place/move  Shift+LMB resize  RMB/Del delete  G grid  Ctrl+Z/Y undo/redo  Ctrl+S save  P playtest  PgUp/PgDn level  Esc exit";
+
+// ==== PLUGIN ====
+
+/// Level editor (AppState::Editor), edits the levels of the selected run in their file format.<br>
+/// Levels are playtested as a run of one level, finishing it comes back to the editor.
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //.
+        .init_resource::<EditorDocument>()
+        .init_resource::<EditorState>()
+        .add_systems(OnEnter(AppState::Editor), (open_editor, spawn_editor_hud))
+        .add_systems(Update, (editor_keyboard, editor_mouse, update_editor_hud, draw_editor).chain()
+            .run_if(in_state(AppState::Editor)))
+        .add_systems(Update, finish_playtest
+            .run_if(in_state(AppState::Game))
+            .run_if(resource_exists::<Playtest>))
+        .add_systems(OnExit(AppState::Editor), despawn_editor_hud)
+        //.
+        ;
+    }
+}
+
+// ==== DOCUMENT ====
+
+/// Objects the editor can place, everything else in a level is kept as it was written
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EditorKind {
+    Obstacle,
+    Coin,
+    Door,
+    Spawn,
+}
+
+impl EditorKind {
+    pub const ALL: [EditorKind; 4] = [EditorKind::Obstacle, EditorKind::Coin, EditorKind::Door, EditorKind::Spawn];
+
+    pub fn from_keyword(keyword: &str) -> Option<EditorKind> {
+        match keyword {
+            "BARRIER" | "OBSTACLE" => Some(EditorKind::Obstacle),
+            "COIN" => Some(EditorKind::Coin),
+            "DOOR" => Some(EditorKind::Door),
+            "PLAYER_POS" => Some(EditorKind::Spawn),
+            _ => None,
+        }
+    }
+
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            EditorKind::Obstacle => "BARRIER",
+            EditorKind::Coin => "COIN",
+            EditorKind::Door => "DOOR",
+            EditorKind::Spawn => "PLAYER_POS",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditorKind::Obstacle => "Obstacle",
+            EditorKind::Coin => "Coin",
+            EditorKind::Door => "Door",
+            EditorKind::Spawn => "Spawn",
+        }
+    }
+
+    /// whether the size is written in the level file
+    pub fn resizable(&self) -> bool {
+        matches!(self, EditorKind::Obstacle | EditorKind::Door)
+    }
+
+    /// size of a newly placed object, in level file units
+    pub fn default_size(&self) -> Vec2 {
+        match self {
+            EditorKind::Obstacle => Vec2::new(50.0, 10.0),
+            EditorKind::Door => Vec2::new(40.0, 80.0),
+            _ => Vec2::ZERO,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            EditorKind::Obstacle => Color::WHITE,
+            EditorKind::Coin => Color::YELLOW,
+            EditorKind::Door => Color::ORANGE,
+            EditorKind::Spawn => EDITOR_SPAWN_COLOR,
+        }
+    }
+}
+
+/// Object as written in a level file, with the top left corner and size in legacy units
+#[derive(Clone, PartialEq, Debug)]
+pub struct EditorObject {
+    pub kind: EditorKind,
+    pub pos: Vec2,
+    pub size: Vec2,
+    /// rest of the line, e.g. the door's arguments
+    pub extra: String,
+}
+
+impl EditorObject {
+    pub fn parse(line: &str) -> Option<EditorObject> {
+        let segs: Vec<&str> = line.split_ascii_whitespace().collect();
+        let kind = EditorKind::from_keyword(segs.first()?)?;
+        let numbers = if kind.resizable() {4} else {2};
+        let values: Vec<f32> = segs.get(1..=numbers)?.iter().map(|s| s.parse().ok()).collect::<Option<_>>()?;
+        Some(EditorObject {
+            kind,
+            pos: Vec2::new(values[0], values[1]),
+            size: if kind.resizable() {Vec2::new(values[2], values[3])} else {Vec2::ZERO},
+            extra: segs[numbers + 1..].join(" "),
+        })
+    }
+
+    pub fn line(&self) -> String {
+        let mut line = format!("{} {:.1} {:.1}", self.kind.keyword(), self.pos.x, self.pos.y);
+        if self.kind.resizable() {
+            line += &format!(" {:.1} {:.1}", self.size.x, self.size.y);
+        }
+        if !self.extra.is_empty() {
+            line += &format!(" {}", self.extra);
+        }
+        line
+    }
+
+    /// size the loader aligns the position with
+    fn alignment(&self) -> Vec2 {
+        match self.kind {
+            EditorKind::Coin => Vec2::new(COIN_SIZE, COIN_SIZE),
+            EditorKind::Spawn => Vec2::new(PLAYER_SIZE, PLAYER_SIZE),
+            _ => self.size,
+        }
+    }
+
+    /// area covered in game, same as spawned by the loader
+    pub fn world_rect(&self) -> Rect {
+        let size = match self.kind {
+            EditorKind::Coin => Vec2::splat(COIN_SIZE * 2.0),
+            EditorKind::Spawn => Vec2::splat(PLAYER_SIZE),
+            _ => self.size * LEGACY_SCALE,
+        };
+        Rect::from_center_size(fix_aligment(self.pos, self.alignment()), size)
+    }
+
+    /// move the object so it's centered on a point in game coords
+    pub fn center_on(&mut self, center: Vec2) {
+        let alignment = self.alignment();
+        self.pos = Vec2::new(
+            center.x / LEGACY_SCALE - alignment.x / 2.0,
+            (PLAYFIELD_HEIGHT - center.y) / LEGACY_SCALE - alignment.y / 2.0,
+        );
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum EditorItem {
+    Object(EditorObject),
+    /// line the editor doesn't handle, written back unchanged
+    Raw(String),
+}
+
+/// Level being edited, with its undo history
+#[derive(Resource, Default)]
+pub struct EditorDocument {
+    /// path of the run
+    pub run: String,
+    pub level: usize,
+    /// number of levels in the run
+    pub levels: usize,
+    pub items: Vec<EditorItem>,
+    /// changed since it was loaded or saved
+    pub dirty: bool,
+    pub undo: Vec<Vec<EditorItem>>,
+    pub redo: Vec<Vec<EditorItem>>,
+}
+
+impl EditorDocument {
+    /// open a level of a run, levels past the last one start empty
+    pub fn load(run: &str, level: usize) -> EditorDocument {
+        let levels = read_dir(run).map_or(0, |ls| level_numbers(ls).len());
+        let path = format!("{}/{}", run, level);
+        let mut buff = String::new();
+        if let Err(e) = File::open(&path).and_then(|mut f| f.read_to_string(&mut buff)) {
+            println!("[EDITOR] Starting an empty level, couldn't read {} due {}", path, e);
+        }
+        let items = buff.lines()
+            .map(|l| EditorObject::parse(l).map_or(EditorItem::Raw(l.to_string()), EditorItem::Object))
+            .collect();
+        println!("[EDITOR] Opened {}", path);
+        EditorDocument {
+            run: run.to_string(),
+            level,
+            levels,
+            items,
+            ..default()
+        }
+    }
+
+    pub fn path(&self) -> String {
+        format!("{}/{}", self.run, self.level)
+    }
+
+    /// the level in its file format
+    pub fn text(&self) -> String {
+        let mut buff = String::new();
+        for item in self.items.iter() {
+            match item {
+                EditorItem::Object(object) => buff += &object.line(),
+                EditorItem::Raw(line) => buff += line,
+            }
+            buff += "\n";
+        }
+        buff
+    }
+
+    /// write to a temporary file first and move it over the level, like the save.<br>
+    /// The temporary file isn't named by a number, so it's never counted as a level.
+    pub fn save(&mut self) -> Result<(), String> {
+        let path = self.path();
+        let temp = format!("{}.tmp", path);
+        File::create(&temp)
+            .and_then(|mut f| f.write_all(self.text().as_bytes()).and_then(|_| f.sync_all()))
+            .and_then(|_| fs::rename(&temp, &path))
+            .map_err(|e| {
+                let _ = fs::remove_file(&temp);
+                format!("Couldn't save {} due {}", path, e)
+            })?;
+        self.dirty = false;
+        self.levels = self.levels.max(self.level + 1);
+        println!("[EDITOR] Saved {}", path);
+        Ok(())
+    }
+
+    /// remember the items as they were before a change
+    pub fn commit(&mut self, before: Vec<EditorItem>) {
+        self.undo.push(before);
+        if self.undo.len() > EDITOR_HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.dirty = true;
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(items) = self.undo.pop() else {return false};
+        self.redo.push(std::mem::replace(&mut self.items, items));
+        self.dirty = true;
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(items) = self.redo.pop() else {return false};
+        self.undo.push(std::mem::replace(&mut self.items, items));
+        self.dirty = true;
+        true
+    }
+
+    /// topmost object at a point in game coords
+    pub fn object_at(&self, point: Vec2) -> Option<usize> {
+        self.items.iter().enumerate().rev().find_map(|(i, item)| match item {
+            EditorItem::Object(object) if object.world_rect().contains(point) => Some(i),
+            _ => None,
+        })
+    }
+
+    pub fn object(&self, index: usize) -> Option<&EditorObject> {
+        match self.items.get(index)? {
+            EditorItem::Object(object) => Some(object),
+            EditorItem::Raw(_) => None,
+        }
+    }
+}
+
+// ==== STATE ====
+
+/// Object being moved or resized with the mouse
+pub struct EditorDrag {
+    pub index: usize,
+    /// cursor position (in game coords) where the drag started
+    pub start: Vec2,
+    pub original: EditorObject,
+    pub resize: bool,
+    /// items before the drag, committed once it ends if anything changed
+    pub before: Vec<EditorItem>,
+}
+
+#[derive(Resource)]
+pub struct EditorState {
+    /// kind of object placed by clicking on an empty spot
+    pub tool: EditorKind,
+    pub snap: bool,
+    pub selected: Option<usize>,
+    pub drag: Option<EditorDrag>,
+    /// result of the last action, shown in the hud
+    pub message: String,
+    /// leaving was asked with unsaved changes, asking again leaves
+    pub leave_warned: bool,
+}
+
+impl Default for EditorState {
+    fn default() -> Self {
+        EditorState {
+            tool: EditorKind::Obstacle,
+            snap: true,
+            selected: None,
+            drag: None,
+            message: String::new(),
+            leave_warned: false,
+        }
+    }
+}
+
+/// Present while the edited level is played, holds the run to go back to
+#[derive(Resource)]
+pub struct Playtest {
+    pub run: String,
+}
+
+fn snap(value: Vec2, on: bool) -> Vec2 {
+    if on {(value / EDITOR_GRID).round() * EDITOR_GRID} else {value}
+}
+
+/// write the level as the only level of a temporary run, with the edited run's info
+fn write_playtest(document: &EditorDocument) -> std::io::Result<()> {
+    let _ = fs::remove_dir_all(PATH_PLAYTEST);
+    fs::create_dir_all(PATH_PLAYTEST)?;
+    if fs::copy(format!("{}/info", document.run), format!("{}/info", PATH_PLAYTEST)).is_err() {
+        File::create(format!("{}/info", PATH_PLAYTEST))?.write_all(b"AUTHOR editor\n")?;
+    }
+    File::create(format!("{}/0", PATH_PLAYTEST))?.write_all(document.text().as_bytes())
+}
+
+// ==== COMPONENTS ====
+
+#[derive(Component)]
+pub struct EditorHud;
+
+// ==== SYSTEMS ====
+
+/// Open the selected run, unless it's already being edited
+pub fn open_editor(
+    mut commands: Commands,
+    mut document: ResMut<EditorDocument>,
+    mut editor: ResMut<EditorState>,
+    mut selected_run: ResMut<SelectedRun>,
+    playtest: Option<Res<Playtest>>,
+    mut camera_query: Query<&mut CameraFocus, With<MainCamera>>,
+) {
+    if let Some(playtest) = playtest {
+        selected_run.0 = playtest.run.clone();
+        commands.remove_resource::<Playtest>();
+    }
+    if document.run != selected_run.0 {
+        *document = EditorDocument::load(&selected_run.0, 0);
+    }
+    editor.selected = None;
+    editor.drag = None;
+    editor.leave_warned = false;
+    if let Ok(mut focus) = camera_query.get_single_mut() {
+        focus.0 = Vec2::new(PLAYFIELD_WIDTH / 2.0, PLAYFIELD_HEIGHT / 2.0);
+    }
+}
+
+pub fn spawn_editor_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let mut style = get_normal_text_style(&asset_server);
+    style.font_size /= 2.0;
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(8.0),
+                    top: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            },
+            EditorHud,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text::from_sections([
+                    TextSection::new("", style.clone()),
+                    TextSection::new(format!("\n{}", EDITOR_HELP), style),
+                ]),
+                ..default()
+            });
+        });
+}
+
+pub fn despawn_editor_hud(mut commands: Commands, hud_query: Query<Entity, With<EditorHud>>) {
+    for entity in hud_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn editor_keyboard(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut document: ResMut<EditorDocument>,
+    mut editor: ResMut<EditorState>,
+    mut selected_run: ResMut<SelectedRun>,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+    mut camera_query: Query<&mut CameraFocus, With<MainCamera>>,
+    time: Res<Time>,
+) {
+    // unsaved changes are kept until the editor is opened for another run
+    if bindings.just_pressed(InputAction::Pause, &keyboard_input) {
+        if document.dirty && !editor.leave_warned {
+            editor.message = "Unsaved changes, save (Ctrl+S) or press Esc again to leave".to_string();
+            editor.leave_warned = true;
+        } else {
+            app_state_next_state.set(AppState::MainMenu);
+        }
+        return;
+    }
+    let ctrl = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let tools = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4];
+    for (key, kind) in tools.iter().zip(EditorKind::ALL) {
+        if keyboard_input.just_pressed(*key) {
+            editor.tool = kind;
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyG) {
+        editor.snap = !editor.snap;
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        if let Some(index) = editor.selected.take() {
+            editor.drag = None;
+            let before = document.items.clone();
+            document.items.remove(index);
+            document.commit(before);
+        }
+    }
+    let undo = ctrl && keyboard_input.just_pressed(KeyCode::KeyZ) && !shift;
+    let redo = ctrl && (keyboard_input.just_pressed(KeyCode::KeyY) || (keyboard_input.just_pressed(KeyCode::KeyZ) && shift));
+    if (undo && document.undo()) || (redo && document.redo()) {
+        editor.selected = None;
+        editor.drag = None;
+    }
+    if ctrl && keyboard_input.just_pressed(KeyCode::KeyS) {
+        editor.message = match document.save() {
+            Ok(_) => format!("Saved {}", document.path()),
+            Err(e) => e,
+        };
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyP) {
+        match write_playtest(&document) {
+            Ok(_) => {
+                commands.insert_resource(Playtest { run: document.run.clone() });
+                selected_run.0 = PATH_PLAYTEST.to_string();
+                app_state_next_state.set(AppState::Game);
+            }
+            Err(e) => editor.message = format!("Couldn't start a playtest due {}", e),
+        }
+    }
+    // one level past the last starts a new one
+    let level = if keyboard_input.just_pressed(KeyCode::PageUp) {
+        document.level.checked_sub(1)
+    } else if keyboard_input.just_pressed(KeyCode::PageDown) {
+        Some(document.level + 1).filter(|l| *l <= document.levels)
+    } else {
+        None
+    };
+    if let Some(level) = level {
+        if document.dirty {
+            editor.message = "Save (Ctrl+S) before switching levels".to_string();
+        } else {
+            *document = EditorDocument::load(&document.run, level);
+            editor.selected = None;
+            editor.message.clear();
+        }
+    }
+    // move the view
+    let mut direction = Vec2::ZERO;
+    if keyboard_input.pressed(KeyCode::ArrowLeft) {direction.x -= 1.0;}
+    if keyboard_input.pressed(KeyCode::ArrowRight) {direction.x += 1.0;}
+    if keyboard_input.pressed(KeyCode::ArrowDown) {direction.y -= 1.0;}
+    if keyboard_input.pressed(KeyCode::ArrowUp) {direction.y += 1.0;}
+    if let (Ok(mut focus), true) = (camera_query.get_single_mut(), direction != Vec2::ZERO) {
+        focus.0 += direction * EDITOR_PAN_SPEED * time.delta_seconds();
+    }
+}
+
+/// Place objects on empty spots, move or resize them by dragging and delete them with the right button
+pub fn editor_mouse(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut document: ResMut<EditorDocument>,
+    mut editor: ResMut<EditorState>,
+) {
+    if mouse_input.just_released(MouseButton::Left) {
+        if let Some(drag) = editor.drag.take() {
+            if document.items != drag.before {
+                document.commit(drag.before);
+            }
+        }
+    }
+    let (Ok(window), Ok((camera, camera_transform))) = (window_query.get_single(), camera_query.get_single()) else {return};
+    // the cursor is relative to the window, the camera only draws into its viewport
+    let viewport = camera.logical_viewport_rect().map_or(Vec2::ZERO, |r| r.min);
+    let Some(cursor) = window.cursor_position()
+        .and_then(|p| camera.viewport_to_world_2d(camera_transform, p - viewport))
+    else {return};
+    let snap_on = editor.snap;
+    if mouse_input.just_pressed(MouseButton::Left) {
+        let index = match document.object_at(cursor) {
+            Some(index) => index,
+            None => {
+                // a level has a single spawn, placing it again moves it
+                let before = document.items.clone();
+                let tool = editor.tool;
+                let spawn = document.items.iter().position(|i| matches!(i, EditorItem::Object(o) if o.kind == EditorKind::Spawn));
+                let mut object = EditorObject {
+                    kind: tool,
+                    pos: Vec2::ZERO,
+                    size: tool.default_size(),
+                    extra: String::new(),
+                };
+                object.center_on(cursor);
+                object.pos = snap(object.pos, snap_on);
+                let index = match spawn {
+                    Some(index) if tool == EditorKind::Spawn => {
+                        document.items[index] = EditorItem::Object(object);
+                        index
+                    }
+                    _ => {
+                        document.items.push(EditorItem::Object(object));
+                        document.items.len() - 1
+                    }
+                };
+                document.commit(before);
+                index
+            }
+        };
+        let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        editor.selected = Some(index);
+        editor.drag = document.object(index).map(|object| EditorDrag {
+            index,
+            start: cursor,
+            original: object.clone(),
+            resize: shift && object.kind.resizable(),
+            before: document.items.clone(),
+        });
+    }
+    if mouse_input.pressed(MouseButton::Left) {
+        if let Some(drag) = editor.drag.as_ref() {
+            // OY is flipped in level files
+            let delta = (cursor - drag.start) / LEGACY_SCALE * Vec2::new(1.0, -1.0);
+            let mut object = drag.original.clone();
+            if drag.resize {
+                object.size = snap(object.size + delta, snap_on).max(Vec2::ONE);
+            } else {
+                object.pos = snap(object.pos + delta, snap_on);
+            }
+            let item = EditorItem::Object(object);
+            if document.items[drag.index] != item {
+                document.items[drag.index] = item;
+            }
+        }
+    }
+    if mouse_input.just_pressed(MouseButton::Right) && editor.drag.is_none() {
+        if let Some(index) = document.object_at(cursor) {
+            let before = document.items.clone();
+            document.items.remove(index);
+            document.commit(before);
+            editor.selected = None;
+        }
+    }
+}
+
+pub fn update_editor_hud(
+    document: Res<EditorDocument>,
+    editor: Res<EditorState>,
+    mut text_query: Query<&mut Text>,
+    hud_query: Query<&Children, With<EditorHud>>,
+) {
+    if !document.is_changed() && !editor.is_changed() {return;}
+    let Ok(children) = hud_query.get_single() else {return};
+    let Some(mut text) = children.first().and_then(|c| text_query.get_mut(*c).ok()) else {return};
+    text.sections[0].value = format!(
+        "{} level {}{}  |  tool: {}  |  grid: {}  {}",
+        Leaderboard::run_name(&document.run),
+        document.level,
+        if document.dirty {"*"} else {""},
+        editor.tool.label(),
+        if editor.snap {"ON"} else {"OFF"},
+        editor.message,
+    );
+}
+
+/// Outline the playfield, the grid and every object
+pub fn draw_editor(mut gizmos: Gizmos, document: Res<EditorDocument>, editor: Res<EditorState>) {
+    let playfield = Vec2::new(PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT);
+    if editor.snap {
+        let step = EDITOR_GRID * LEGACY_SCALE;
+        let mut x = step;
+        while x < playfield.x {
+            gizmos.line_2d(Vec2::new(x, 0.0), Vec2::new(x, playfield.y), EDITOR_GRID_COLOR);
+            x += step;
+        }
+        let mut y = step;
+        while y < playfield.y {
+            gizmos.line_2d(Vec2::new(0.0, y), Vec2::new(playfield.x, y), EDITOR_GRID_COLOR);
+            y += step;
+        }
+    }
+    gizmos.rect_2d(playfield / 2.0, 0.0, playfield, Color::GRAY);
+    for (i, item) in document.items.iter().enumerate() {
+        let EditorItem::Object(object) = item else {continue};
+        let color = if editor.selected == Some(i) {EDITOR_SELECTED_COLOR} else {object.kind.color()};
+        let rect = object.world_rect();
+        match object.kind {
+            EditorKind::Coin => {gizmos.circle_2d(rect.center(), COIN_SIZE, color);}
+            _ => gizmos.rect_2d(rect.center(), 0.0, rect.size(), color),
+        }
+    }
+}
+
+/// Go back to the editor once the edited level was finished
+pub fn finish_playtest(
+    mut event_reader: EventReader<LevelCompleteEvent>,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+    mut hud_next_state: ResMut<NextState<HudState>>,
+) {
+    if event_reader.read().any(|e| e.last) {
+        app_state_next_state.set(AppState::Editor);
+        hud_next_state.set(HudState::None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn objects_round_trip_through_their_lines() {
+        let lines = [
+            "BARRIER 0.0 460.0 640.0 20.0",
+            "COIN 150.0 280.0",
+            "PLAYER_POS 0.0 430.0",
+            // the door's arguments are kept as they were
+            "DOOR 600.0 380.0 40.0 80.0 1 5",
+        ];
+        for line in lines {
+            let object = EditorObject::parse(line).unwrap();
+            assert_eq!(object.line(), line);
+            assert_eq!(EditorObject::parse(&object.line()), Some(object));
+        }
+        let door = EditorObject::parse("DOOR 600 380 40 80 1 5").unwrap();
+        assert_eq!(door.extra, "1 5");
+        assert_eq!(door.line(), "DOOR 600.0 380.0 40.0 80.0 1 5");
+        // OBSTACLE is read as a barrier
+        assert_eq!(EditorObject::parse("OBSTACLE 1 2 3 4").unwrap().line(), "BARRIER 1.0 2.0 3.0 4.0");
+    }
+
+    #[test]
+    fn other_lines_are_written_back_unchanged() {
+        let raw = ["PLAYER_SIZE 30.0 30.0", "", "SPRING 10 20 30 5 -1", "COIN 1", "BARRIER a b c d"];
+        for line in raw {
+            assert_eq!(EditorObject::parse(line), None, "{}", line);
+        }
+        let text = ["BARRIER 0.0 460.0 640.0 20.0", raw[0], raw[1], "DOOR 600.0 380.0 40.0 80.0 1 5", raw[2]].join("\n") + "\n";
+        let items = text.lines()
+            .map(|l| EditorObject::parse(l).map_or(EditorItem::Raw(l.to_string()), EditorItem::Object))
+            .collect();
+        let document = EditorDocument { items, ..default() };
+        assert_eq!(document.text(), text);
+    }
+
+    fn raw(line: &str) -> Vec<EditorItem> {
+        vec![EditorItem::Raw(line.to_string())]
+    }
+
+    #[test]
+    fn history_keeps_the_last_changes() {
+        let mut document = EditorDocument::default();
+        for i in 0..EDITOR_HISTORY + 5 {
+            let before = std::mem::replace(&mut document.items, raw(&(i + 1).to_string()));
+            document.commit(before);
+        }
+        assert_eq!(document.undo.len(), EDITOR_HISTORY);
+        for _ in 0..EDITOR_HISTORY {
+            assert!(document.undo());
+        }
+        assert!(!document.undo());
+        // the oldest changes were forgotten
+        assert_eq!(document.items, raw("5"));
+        assert_eq!(document.redo.len(), EDITOR_HISTORY);
+    }
+
+    #[test]
+    fn new_change_clears_redo() {
+        let mut document = EditorDocument { items: raw("a"), ..default() };
+        let before = std::mem::replace(&mut document.items, raw("b"));
+        document.commit(before);
+        assert!(document.undo());
+        assert_eq!(document.items, raw("a"));
+        assert!(document.redo());
+        assert_eq!(document.items, raw("b"));
+        assert!(document.undo());
+        let before = std::mem::replace(&mut document.items, raw("c"));
+        document.commit(before);
+        assert!(!document.redo());
+        assert_eq!(document.items, raw("c"));
+        assert!(document.undo());
+        assert_eq!(document.items, raw("a"));
+    }
+
+    #[test]
+    fn objects_center_on_their_world_rect() {
+        let lines = ["BARRIER 0 0 50 10", "COIN 0 0", "PLAYER_POS 0 0", "DOOR 0 0 40 80"];
+        let points = [Vec2::new(300.0, 400.0), Vec2::new(123.0, 457.0), Vec2::new(-40.0, 999.0)];
+        for line in lines {
+            let mut object = EditorObject::parse(line).unwrap();
+            for point in points {
+                object.center_on(point);
+                assert!(object.world_rect().center().abs_diff_eq(point, 0.001), "{} at {}", line, point);
+                // snapping moves it by at most half a grid step
+                object.pos = snap(object.pos, true);
+                assert_eq!(object.pos % EDITOR_GRID, Vec2::ZERO);
+                let offset = (object.world_rect().center() - point).abs();
+                assert!(offset.max_element() <= EDITOR_GRID * LEGACY_SCALE / 2.0 + 0.001, "{} at {}", line, point);
+                assert_eq!(snap(object.pos, true), object.pos);
+                assert_eq!(snap(point, false), point);
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+
+// ==== CONSTANTS ====
+pub const PLAYER_DASH_SPEED: f32 = 14.0;
+/// time (in ticks) the dash overrides the player's movement
+pub const PLAYER_DASH_TIME: u32 = 10;
+pub const PLAYER_POUND_SPEED: f32 = 16.0;
+
+// ==== ABILITIES ====
+
+/// Abilities which can be unlocked by a run's info file or a level file
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ability {
+    DoubleJump,
+    AirDash,
+    GroundPound,
+}
+
+impl Ability {
+    /// name used by run and level files
+    pub fn from_name(name: &str) -> Option<Ability> {
+        match name {
+            "DOUBLE_JUMP" => Some(Ability::DoubleJump),
+            "AIR_DASH" => Some(Ability::AirDash),
+            "GROUND_POUND" => Some(Ability::GroundPound),
+            _ => None,
+        }
+    }
+
+    /// parse `ABILITY <name> [charges]` arguments
+    pub fn parse(segs: &[&str]) -> Option<(Ability, u32)> {
+        let ability = Ability::from_name(segs.get(1)?)?;
+        let charges = match segs.get(2) {
+            Some(c) => c.parse().ok()?,
+            None => 1,
+        };
+        Some((ability, charges))
+    }
+}
+
+// ==== COMPONENTS ====
+
+/// Extra jumps in the air, restored on landing
+#[derive(Component)]
+pub struct DoubleJump {
+    pub charges: u32,
+    pub max: u32,
+}
+
+/// Horizontal dashes in the air, restored on landing
+#[derive(Component)]
+pub struct AirDash {
+    pub charges: u32,
+    pub max: u32,
+    /// ticks left of the current dash
+    pub counter: u32,
+    /// direction the player last moved in, -1.0 or 1.0
+    pub facing: f32,
+}
+
+/// Fast drop straight down, lasts until landing
+#[derive(Component, Default)]
+pub struct GroundPound {
+    pub active: bool,
+}
+
+// ==== FUNCTIONS ====
+
+/// Replace the player's abilities with the given ones.<br>
+/// Charges of an ability listed more than once are summed up.
+pub fn grant_abilities(player: &mut EntityCommands, abilities: &[(Ability, u32)]) {
+    player.remove::<(DoubleJump, AirDash, GroundPound)>();
+    let charges = |ability: Ability| -> u32 {
+        abilities.iter().filter(|(a, _)| *a == ability).map(|(_, c)| c).sum()
+    };
+    let double_jump = charges(Ability::DoubleJump);
+    if double_jump > 0 {
+        player.insert(DoubleJump { charges: double_jump, max: double_jump });
+    }
+    let air_dash = charges(Ability::AirDash);
+    if air_dash > 0 {
+        player.insert(AirDash { charges: air_dash, max: air_dash, counter: 0, facing: 1.0 });
+    }
+    if charges(Ability::GroundPound) > 0 {
+        player.insert(GroundPound::default());
+    }
+}
@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+
+use crate::{camera::ScreenShake, data::Settings, AppState, SimulationState};
+
+use super::{handle_player_obstacle_collision, kill_fallen_player, spawn_particles, PlayerDeathEvent, ParticleEmitter, PlayerComponent};
+
+// ==== CONSTANTS ====
+/// impacts weaker than this (landing at walking speed, standing still) cause no effects
+pub const HARD_LANDING_STRENGTH: f32 = 0.5;
+/// trauma added to the screen shake per point of impact strength
+pub const IMPACT_SHAKE: f32 = 0.5;
+pub const DEATH_SHAKE: f32 = 0.6;
+/// time (in ticks) of the player's squash after landing
+pub const SQUASH_TIME: u32 = 10;
+/// squash of the hardest landing, as a part of the player's size
+pub const SQUASH_MAX_AMOUNT: f32 = 0.35;
+
+// ==== PLUGIN ====
+
+/// Feedback for impacts and deaths of the player: screen shake, squash and particles.<br>
+/// Each can be turned off in settings.
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //.
+        .add_event::<ImpactEvent>()
+        .add_systems(FixedUpdate, (
+            handle_impact_event.after(handle_player_obstacle_collision),
+            handle_death_event.after(kill_fallen_player),
+            animate_squash
+        ).run_if(in_state(AppState::Game)).run_if(in_state(SimulationState::Running)))
+        //.
+        ;
+    }
+}
+
+// ==== EVENTS & COMPONENTS ====
+
+/// Player hit an obstacle's top or bottom, sent by player_obstacle_collision
+#[derive(Event)]
+pub struct ImpactEvent {
+    /// point of contact
+    pub position: Vec2,
+    /// direction pointing away from the obstacle
+    pub normal: Vec2,
+    /// speed at impact divided by VOLUME_DETERMINATION_BASE, the same value used for the sound
+    pub strength: f32,
+}
+
+/// Squash and stretch of a landing player, scales back to normal over SQUASH_TIME
+#[derive(Component)]
+pub struct Squash {
+    pub amount: f32,
+    pub counter: u32,
+}
+
+// ==== SYSTEMS ====
+
+pub fn handle_impact_event(
+    mut event_reader: EventReader<ImpactEvent>,
+    mut commands: Commands,
+    player_query: Query<Entity, With<PlayerComponent>>,
+    mut shake: ResMut<ScreenShake>,
+    settings: Res<Settings>,
+) {
+    // the player can touch a few obstacles at once, only the hardest impact counts
+    let Some(impact) = event_reader.read()
+        .filter(|e| e.strength >= HARD_LANDING_STRENGTH)
+        .max_by(|a, b| a.strength.total_cmp(&b.strength))
+    else {return};
+    if settings.screen_shake {
+        shake.add(impact.strength * IMPACT_SHAKE);
+    }
+    if !settings.impact_effects {return;}
+    // only landings squash the player
+    if impact.normal.y > 0.0 {
+        if let Ok(player) = player_query.get_single() {
+            commands.entity(player).insert(Squash {
+                amount: (impact.strength * SQUASH_MAX_AMOUNT).min(SQUASH_MAX_AMOUNT),
+                counter: SQUASH_TIME,
+            });
+        }
+    }
+    spawn_particles(&mut commands, ParticleEmitter::landing(impact.normal, impact.strength), impact.position);
+}
+
+pub fn handle_death_event(
+    mut event_reader: EventReader<PlayerDeathEvent>,
+    mut commands: Commands,
+    mut shake: ResMut<ScreenShake>,
+    settings: Res<Settings>,
+) {
+    for death in event_reader.read() {
+        if settings.screen_shake {
+            shake.add(DEATH_SHAKE);
+        }
+        // part of the gameplay feedback, so not behind impact_effects
+        spawn_particles(&mut commands, ParticleEmitter::death(), death.position);
+    }
+}
+
+pub fn animate_squash(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &mut Transform, &mut Squash)>,
+) {
+    for (entity, mut transform, mut squash) in player_query.iter_mut() {
+        let amount = squash.amount * squash.counter as f32 / SQUASH_TIME as f32;
+        transform.scale.x = 1.0 + amount;
+        transform.scale.y = 1.0 - amount;
+        if squash.counter > 0 {
+            squash.counter -= 1;
+        } else {
+            commands.entity(entity).remove::<Squash>();
+        }
+    }
+}
@@ -1,11 +1,12 @@
 
-use bevy::{prelude::*, time::Stopwatch, utils::hashbrown::Equivalent};
+use bevy::prelude::*;
 
 use crate::{
-    menu::styles::{get_title_text_style, COUNTER_STARS_STYLE, GAME_UI_STYLE}, AppState, HudState, SimulationState
+    data::Settings,
+    menu::styles::{get_normal_text_style, get_title_text_style, COUNTER_ENEMIES_STYLE, COUNTER_STARS_STYLE, GAME_UI_STYLE}, AppState, HudState
 };
 
-use super::coin::Score;
+use super::{coin::Score, RunProgress, AirDash, DoubleJump, GroundPound, PlayerComponent};
 
 pub struct HudPlugin;
 
@@ -14,7 +15,7 @@ impl Plugin for HudPlugin {
         app
             //
             .add_systems(OnEnter(AppState::Game), spawn_player_hud)
-            .add_systems(Update, (update_score_label,update_time).run_if(in_state(AppState::Game)))
+            .add_systems(Update, (update_score_label,update_time,update_abilities_label,apply_hud_settings).run_if(in_state(AppState::Game)))
             .add_systems(OnExit(AppState::Game), despawn_player_hud)
 
             // hide during score screen
@@ -35,9 +36,11 @@ pub struct PlayerHud;
 pub struct HudPlayerScore;
 
 #[derive(Component)]
-pub struct HudPlayerTime {
-    time: Stopwatch
-}
+pub struct HudPlayerAbilities;
+
+/// Shows the run time of RunProgress
+#[derive(Component)]
+pub struct HudPlayerTime;
 
 
 pub fn spawn_player_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
@@ -73,7 +76,7 @@ pub fn build_player_hud(commands: &mut Commands, asset_server: &Res<AssetServer>
                             text: Text {
                                 sections: vec![TextSection::new(
                                     "0",
-                                    get_title_text_style(&asset_server),
+                                    get_title_text_style(asset_server),
                                 )],
                                 justify: JustifyText::Center,
                                 linebreak_behavior: bevy::text::BreakLineOn::NoWrap,
@@ -88,16 +91,36 @@ pub fn build_player_hud(commands: &mut Commands, asset_server: &Res<AssetServer>
                             text: Text {
                                 sections: vec![TextSection::new(
                                     "0",
-                                    get_title_text_style(&asset_server),
+                                    get_title_text_style(asset_server),
                                 )],
                                 justify: JustifyText::Center,
                                 linebreak_behavior: bevy::text::BreakLineOn::NoWrap,
                             },
                             ..default()
                         },
-                        HudPlayerTime {
-                            time: Stopwatch::new()
+                        HudPlayerTime,
+                    ));
+                });
+            // Abilities
+            parent
+                .spawn(NodeBundle {
+                    style: COUNTER_ENEMIES_STYLE,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle {
+                            text: Text {
+                                sections: vec![TextSection::new(
+                                    "",
+                                    get_normal_text_style(asset_server),
+                                )],
+                                justify: JustifyText::Right,
+                                linebreak_behavior: bevy::text::BreakLineOn::NoWrap,
+                            },
+                            ..default()
                         },
+                        HudPlayerAbilities {},
                     ));
                 });
         })
@@ -125,14 +148,47 @@ pub fn update_score_label(
 }
 
 pub fn update_time(
-    mut time_ui_query: Query<(&mut Text, &mut HudPlayerTime), With<HudPlayerTime>>,
-    game_time: Res<Time>,
-    simulation_state: Res<State<SimulationState>>,
+    mut time_ui_query: Query<&mut Text, With<HudPlayerTime>>,
+    progress: Res<RunProgress>,
+) {
+    if let Ok(mut text) = time_ui_query.get_single_mut() {
+        text.sections[0].value = ((progress.time*100.0).floor()/100.0).to_string()+"s";
+    }
+}
+#[allow(clippy::type_complexity)]
+pub fn update_abilities_label(
+    mut abilities_ui_query: Query<&mut Text, With<HudPlayerAbilities>>,
+    player_query: Query<(Option<&DoubleJump>, Option<&AirDash>, Option<&GroundPound>), With<PlayerComponent>>,
 ) {
-    if let Ok((mut text, mut time)) = time_ui_query.get_single_mut() {
-        if simulation_state.equivalent(&SimulationState::Running) {
-            time.time.tick(game_time.delta());
-        };
-        text.sections[0].value = ((time.time.elapsed_secs()*100.0).floor()/100.0).to_string()+"s";
+    if let (Ok(mut text), Ok((double_jump, dash, pound))) = (abilities_ui_query.get_single_mut(), player_query.get_single()) {
+        let mut labels = Vec::new();
+        if let Some(double_jump) = double_jump {
+            labels.push(format!("Jump {}/{}", double_jump.charges, double_jump.max));
+        }
+        if let Some(dash) = dash {
+            labels.push(format!("Dash {}/{}", dash.charges, dash.max));
+        }
+        if let Some(pound) = pound {
+            labels.push(if pound.active {"Pound!".to_string()} else {"Pound".to_string()});
+        }
+        let label = labels.join("  ");
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
     }
-}
\ No newline at end of file
+}
+
+/// Hide the timer and abilities when turned off in settings
+pub fn apply_hud_settings(
+    mut time_query: Query<&mut Visibility, (With<HudPlayerTime>, Without<HudPlayerAbilities>)>,
+    mut abilities_query: Query<&mut Visibility, (With<HudPlayerAbilities>, Without<HudPlayerTime>)>,
+    settings: Res<Settings>,
+) {
+    let shown = |b: bool| if b {Visibility::Inherited} else {Visibility::Hidden};
+    for mut visibility in time_query.iter_mut() {
+        visibility.set_if_neq(shown(settings.show_timer));
+    }
+    for mut visibility in abilities_query.iter_mut() {
+        visibility.set_if_neq(shown(settings.show_abilities));
+    }
+}
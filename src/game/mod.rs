@@ -6,6 +6,27 @@ pub use player::*;
 mod objects;
 pub use objects::*;
 
+mod abilities;
+pub use abilities::*;
+
+mod effects;
+pub use effects::*;
+
+mod particles;
+pub use particles::*;
+
+mod progress;
+pub use progress::*;
+
+mod replay;
+pub use replay::*;
+
+mod share;
+pub use share::*;
+
+mod reach;
+pub use reach::*;
+
 
 mod hud;
 pub use hud::*;
@@ -25,7 +46,7 @@ impl Plugin for GamePlugin {
         app
         //
         .add_systems(OnEnter(AppState::Game), start_running)
-        .add_plugins((PlayerPlugin,GameObjectsPlugin,HudPlugin,PauseMenuPlugin,LoaderPlugin))
+        .add_plugins((PlayerPlugin,GameObjectsPlugin,HudPlugin,PauseMenuPlugin,LoaderPlugin,EffectsPlugin,ParticlesPlugin,ProgressPlugin,ReplayPlugin))
         //
         //.add_systems(Update, test_collision)
         //
@@ -53,6 +74,17 @@ pub struct CollisionSides(pub [bool;4]);
 /// JumpLock set to true disallows jumping
 pub struct JumpLock(pub bool);
 
+#[derive(Component, Default)]
+/// Ticks counted for coyote time and jump buffering
+pub struct JumpTimers {
+    /// ticks since the player last touched the ground
+    pub since_ground: u32,
+    /// ticks since an unperformed jump press, if any
+    pub buffered: Option<u32>,
+    /// was the buffered jump already released
+    pub released: bool,
+}
+
 // ==== FUNCTIONS ====
 
 /// collision between rectangles.<br>uses bevy coords for pos
@@ -1,8 +1,9 @@
-use std::default;
 
 use bevy::prelude::*;
 
-use crate::{game::{collide, PlayerComponent, Size}, AppState, SimulationState};
+use crate::{audio::{PlaySoundEvent, Sound}, game::{collide, handle_player_obstacle_collision, spawn_particles, ParticleEmitter, PlayerComponent, Size}, AppState, SimulationState};
+
+use super::{door::DoorComponent, switch::Inactive};
 
 
 #[derive(Component)]
@@ -15,8 +16,9 @@ impl Plugin for CoinPLugin {
         app
         //.
         .add_systems(OnEnter(AppState::Game), insert_coin_resource)  
-        .add_systems(Update, 
+        .add_systems(FixedUpdate, 
             coin_player_collide
+                .after(handle_player_obstacle_collision)
                 .run_if(in_state(AppState::Game))
                 .run_if(in_state(SimulationState::Running))
         )    
@@ -44,18 +46,29 @@ fn remove_coin_resource(
     commands.remove_resource::<Score>();
 }
 
-fn coin_player_collide(
+#[allow(clippy::type_complexity)]
+pub fn coin_player_collide(
     mut commands: Commands,
     mut player_query: Query<(&mut Transform, & Size), With<PlayerComponent>>,
-    coin_query: Query<(Entity, &Transform, &Size), (With<CoinComponent>, Without<PlayerComponent>)>,
+    coin_query: Query<(Entity, &Transform, &Size), (With<CoinComponent>, Without<PlayerComponent>, Without<Inactive>)>,
+    door_query: Query<&Transform, (With<DoorComponent>, Without<PlayerComponent>, Without<Inactive>)>,
     mut score_resource: ResMut<Score>,
+    mut sounds: EventWriter<PlaySoundEvent>,
 ) {
     if let Ok((pt, ps)) = player_query.get_single_mut() {
     for (oe,ot,os) in coin_query.iter() {
         if collide(pt.translation.xy(), ps.0, ot.translation.xy(), os.0) {
-            //TODO play_coin_sound();
+            sounds.send(PlaySoundEvent::new(Sound::Coin));
+            spawn_particles(&mut commands, ParticleEmitter::coin(), ot.translation.xy());
             score_resource.current+=1;
             commands.get_entity(oe).unwrap().despawn();
+            // the coin which opens the doors, levels needing none start open
+            if score_resource.current == score_resource.needed {
+                sounds.send(PlaySoundEvent::new(Sound::Door));
+                for transform in door_query.iter() {
+                    spawn_particles(&mut commands, ParticleEmitter::door(), transform.translation.xy());
+                }
+            }
         }
     }
 }
@@ -1,8 +1,8 @@
 use bevy::prelude::*;
 
-use crate::{game::{collide, LoadLevelEvent, PlayerComponent, RunData, Size}, menu::SpawnScoreMenuEvent, AppState, SimulationState};
+use crate::{data::SaveData, game::{collide, rate_finish, tick_run_progress, LevelCompleteEvent, LoadLevelEvent, PlayerComponent, RunData, RunProgress, Size}, menu::SpawnScoreMenuEvent, AppState, SimulationState};
 
-use super::coin::Score;
+use super::{coin::{coin_player_collide, Score}, switch::{handle_signal_event, Inactive}};
 
 #[derive(Component)]
 pub struct DoorComponent;
@@ -13,8 +13,12 @@ impl Plugin for DoorPlugin {
     fn build(&self, app: &mut App) {
         app
         //.
-        .add_systems(Update, 
+        // on ticks like the player, so replays finish on the same tick
+        .add_systems(FixedUpdate,
             door_player_collide
+            .after(coin_player_collide)
+            .after(handle_signal_event)
+            .after(tick_run_progress)
             .run_if(in_state(AppState::Game))
             .run_if(in_state(SimulationState::Running))
         )
@@ -23,14 +27,17 @@ impl Plugin for DoorPlugin {
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn door_player_collide(
     mut player_query: Query<(&mut Transform, &Size), With<PlayerComponent>>,
-    coin_query: Query<(&Transform, &Size), (With<DoorComponent>, Without<PlayerComponent>)>,
+    coin_query: Query<(&Transform, &Size), (With<DoorComponent>, Without<PlayerComponent>, Without<Inactive>)>,
     mut score_resource: ResMut<Score>,
     run_resource: Res<RunData>,
     mut event_writer_next: EventWriter<LoadLevelEvent>,
     mut event_writer_menu: EventWriter<SpawnScoreMenuEvent>,
-    game_time: Res<Time>,
+    mut event_writer_complete: EventWriter<LevelCompleteEvent>,
+    mut progress: ResMut<RunProgress>,
+    save: Res<SaveData>,
 ) {
     if let Ok((pt, ps)) = player_query.get_single_mut() {
         for (ot, os) in coin_query.iter() {
@@ -38,6 +45,31 @@ fn door_player_collide(
                 && score_resource.current >= score_resource.needed // greater or equal allows for levels with variable paths
             {
                 println!("Level Won");
+                // next was already advanced when this level was loaded
+                let (level, last) = match run_resource.next {
+                    crate::game::loader::NextLevel::Next(number) => (number.saturating_sub(1), false),
+                    crate::game::loader::NextLevel::Finish => (run_resource.len.saturating_sub(1), true),
+                };
+                let level_time = progress.level_time();
+                let split = progress.time;
+                progress.splits.push(split);
+                progress.coins += score_resource.current;
+                let level_thresholds = run_resource.level_medals.get(level).map(Vec::as_slice).unwrap_or_default();
+                let level_medal = rate_finish(level_thresholds, level_time, score_resource.current, progress.level_deaths);
+                let run_medal = if last {rate_finish(&run_resource.medals, progress.time, progress.coins, progress.deaths)} else {None};
+                progress.level_deaths = 0;
+                event_writer_complete.send(LevelCompleteEvent {
+                    run: run_resource.path.clone(),
+                    level,
+                    last,
+                    level_time,
+                    level_coins: score_resource.current,
+                    level_medal,
+                    run_time: progress.time,
+                    run_coins: progress.coins,
+                    splits: progress.splits.clone(),
+                    run_medal,
+                });
                 match &run_resource.next {
                     crate::game::loader::NextLevel::Next(number) => {
                         event_writer_next.send(LoadLevelEvent {
@@ -45,9 +77,16 @@ fn door_player_collide(
                         });
                     }
                     crate::game::loader::NextLevel::Finish => {
+                        // the save isn't updated yet, so it still holds the results to beat
                         event_writer_menu.send( SpawnScoreMenuEvent {
-                            points: score_resource.current,
-                            time: game_time.elapsed_seconds()
+                            run: run_resource.path.clone(),
+                            points: progress.coins,
+                            time: progress.time,
+                            splits: progress.splits.clone(),
+                            par: run_resource.par.clone(),
+                            deaths: progress.deaths,
+                            medal: run_medal,
+                            previous: save.runs.get(&run_resource.path).cloned().unwrap_or_default(),
                         });
                     }
                 }
@@ -2,10 +2,14 @@ use bevy::app::Plugin;
 use coin::CoinPLugin;
 use door::DoorPlugin;
 use obstacle::ObstaclePlugin;
+use spring::SpringPlugin;
+use switch::SwitchPlugin;
 
 pub mod coin;
 pub mod door;
 pub mod obstacle;
+pub mod spring;
+pub mod switch;
 
 pub struct GameObjectsPlugin;
 
@@ -17,7 +21,9 @@ impl Plugin for GameObjectsPlugin {
             (
                 CoinPLugin,
                 ObstaclePlugin,
-                DoorPlugin
+                DoorPlugin,
+                SwitchPlugin,
+                SpringPlugin
             )
         )
         //.
@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+
+use crate::{game::{collide, handle_player_obstacle_collision, Acceleration, GravityCounter, JumpLock, PlayerComponent, Size}, AppState, SimulationState};
+
+use super::switch::Inactive;
+
+// ==== CONSTANTS ====
+pub const SPRING_COLOR: Color = Color::rgb(0.6, 0.3, 0.9);
+/// default time (in ticks) before a spring can launch the player again
+pub const SPRING_COOLDOWN: u32 = 30;
+/// time (in ticks) of the squash animation
+pub const SPRING_SQUASH_TIME: u32 = 12;
+/// how much of its height the spring loses at the peak of the squash
+pub const SPRING_SQUASH_AMOUNT: f32 = 0.5;
+
+// ==== PLUGIN ====
+
+pub struct SpringPlugin;
+
+impl Plugin for SpringPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //.
+        .add_systems(FixedUpdate,
+            (spring_player_collide.after(handle_player_obstacle_collision),animate_springs)
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(SimulationState::Running))
+        )
+        //.
+        ;
+    }
+}
+
+// ==== COMPONENTS ====
+
+#[derive(Component)]
+pub struct SpringComponent {
+    /// launch applied to the player's acceleration
+    pub impulse: Vec2,
+    /// time (in ticks) between launches
+    pub cooldown: u32,
+    /// ticks left until the spring is usable again
+    pub cooldown_counter: u32,
+    /// ticks left of the squash animation
+    pub squash_counter: u32,
+}
+
+impl SpringComponent {
+    pub fn new(impulse: Vec2, cooldown: u32) -> Self {
+        SpringComponent { impulse, cooldown, cooldown_counter: 0, squash_counter: 0 }
+    }
+}
+
+// ==== SYSTEMS ====
+
+/// Launch the player when they land on the launching side of a spring
+#[allow(clippy::type_complexity)]
+fn spring_player_collide(
+    mut player_query: Query<(&Transform, &Size, &mut Acceleration, &mut JumpLock, &mut GravityCounter), With<PlayerComponent>>,
+    mut spring_query: Query<(&Transform, &Size, &mut SpringComponent), (Without<PlayerComponent>, Without<Inactive>)>,
+) {
+    if let Ok((pt, ps, mut acc, mut jump_lock, mut gravity)) = player_query.get_single_mut() {
+        for (st, ss, mut spring) in spring_query.iter_mut() {
+            if spring.cooldown_counter > 0 {
+                spring.cooldown_counter -= 1;
+                continue;
+            }
+            // only the side the impulse points to is bouncy
+            let side = (pt.translation.xy() - st.translation.xy()).dot(spring.impulse);
+            if side > 0.0 && collide(pt.translation.xy(), ps.0, st.translation.xy(), ss.0) {
+                acc.0.x += spring.impulse.x;
+                acc.0.y += spring.impulse.y;
+                // no jumping or floating out of a launch
+                jump_lock.0 = true;
+                gravity.0 = 0;
+                spring.cooldown_counter = spring.cooldown;
+                spring.squash_counter = SPRING_SQUASH_TIME;
+            }
+        }
+    }
+}
+
+/// Squash springs right after a launch and let them stretch back
+fn animate_springs(mut spring_query: Query<(&mut Transform, &mut SpringComponent)>) {
+    for (mut transform, mut spring) in spring_query.iter_mut() {
+        let squash = SPRING_SQUASH_AMOUNT * spring.squash_counter as f32 / SPRING_SQUASH_TIME as f32;
+        transform.scale.y = 1.0 - squash;
+        if spring.squash_counter > 0 {
+            spring.squash_counter -= 1;
+        }
+    }
+}
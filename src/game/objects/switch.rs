@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+
+use crate::{game::{collide, handle_player_obstacle_collision, Level, PlayerComponent, Size}, AppState, SimulationState};
+
+pub const SWITCH_OFF_COLOR: Color = Color::rgb(0.8, 0.2, 0.2);
+pub const SWITCH_ON_COLOR: Color = Color::rgb(0.2, 0.8, 0.2);
+
+// ==== PLUGIN ====
+
+pub struct SwitchPlugin;
+
+impl Plugin for SwitchPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //.
+        .add_event::<SignalEvent>()
+        .add_systems(FixedUpdate,
+            (switch_player_collide,handle_signal_event).chain()
+                .after(handle_player_obstacle_collision)
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(SimulationState::Running))
+        )
+        //.
+        ;
+    }
+}
+
+// ==== COMPONENTS ====
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SwitchKind {
+    /// toggles its signal every time the player touches it
+    Lever,
+    /// keeps its signal on only while the player stands on it
+    Plate,
+}
+
+#[derive(Component)]
+pub struct SwitchComponent {
+    pub kind: SwitchKind,
+    pub signal: String,
+    pub active: bool,
+    /// was the player touching the switch last frame
+    pub touching: bool,
+}
+
+impl SwitchComponent {
+    pub fn new(kind: SwitchKind, signal: String) -> Self {
+        SwitchComponent { kind, signal, active: false, touching: false }
+    }
+}
+
+/// Object listening to a signal.<br>
+/// It's present (visible and collidable) only while the signal state equals `present_when`
+#[derive(Component)]
+pub struct Triggerable {
+    pub signal: String,
+    pub present_when: bool,
+}
+
+/// Marks objects that are currently switched off, collision systems skip them
+#[derive(Component)]
+pub struct Inactive;
+
+// ==== EVENTS ====
+
+#[derive(Event)]
+pub struct SignalEvent {
+    pub signal: String,
+    pub active: bool,
+}
+
+// ==== SYSTEMS ====
+
+pub fn switch_player_collide(
+    player_query: Query<(&Transform, &Size), With<PlayerComponent>>,
+    mut switch_query: Query<(&Transform, &Size, &mut SwitchComponent, &Handle<ColorMaterial>), Without<PlayerComponent>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut event_writer: EventWriter<SignalEvent>,
+) {
+    if let Ok((pt, ps)) = player_query.get_single() {
+        for (st, ss, mut switch, material) in switch_query.iter_mut() {
+            let touching = collide(pt.translation.xy(), ps.0, st.translation.xy(), ss.0);
+            let active = match switch.kind {
+                SwitchKind::Lever => if touching && !switch.touching {!switch.active} else {switch.active},
+                SwitchKind::Plate => touching,
+            };
+            switch.touching = touching;
+            if active != switch.active {
+                switch.active = active;
+                println!("[SIGNAL] '{}' set to {}", switch.signal, active);
+                event_writer.send(SignalEvent { signal: switch.signal.clone(), active });
+                if let Some(m) = materials.get_mut(material) {
+                    m.color = if active {SWITCH_ON_COLOR} else {SWITCH_OFF_COLOR};
+                }
+            }
+        }
+    }
+}
+
+/// Routes signals to the objects of the current level
+pub fn handle_signal_event(
+    mut commands: Commands,
+    mut event_reader: EventReader<SignalEvent>,
+    level_query: Query<&Children, With<Level>>,
+    mut object_query: Query<(&Triggerable, &mut Visibility)>,
+) {
+    for event in event_reader.read() {
+        for children in level_query.iter() {
+            for &child in children.iter() {
+                if let Ok((trigger, mut visibility)) = object_query.get_mut(child) {
+                    if trigger.signal != event.signal {continue;}
+                    set_presence(&mut commands, child, &mut visibility, event.active == trigger.present_when);
+                }
+            }
+        }
+    }
+}
+
+/// shows/hides the object and toggles its collision
+pub fn set_presence(commands: &mut Commands, entity: Entity, visibility: &mut Visibility, present: bool) {
+    if present {
+        *visibility = Visibility::Inherited;
+        commands.entity(entity).remove::<Inactive>();
+    } else {
+        *visibility = Visibility::Hidden;
+        commands.entity(entity).insert(Inactive);
+    }
+}
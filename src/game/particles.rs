@@ -0,0 +1,226 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use rand::random;
+
+use crate::{AppState, SimulationState};
+
+// ==== PLUGIN ====
+
+/// Bursts of small fading squares.<br>
+/// Spawn a ParticleEmitter (e.g. with `spawn_particles`) and it's replaced by its particles.
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //.
+        .add_systems(Update, emit_particles.run_if(in_state(AppState::Game)))
+        .add_systems(FixedUpdate, update_particles
+            .run_if(in_state(AppState::Game))
+            .run_if(in_state(SimulationState::Running)))
+        .add_systems(OnExit(AppState::Game), despawn_particles)
+        //.
+        ;
+    }
+}
+
+// ==== COMPONENTS ====
+
+/// One burst of particles, emitted from the entity's position
+#[derive(Component, Clone)]
+pub struct ParticleEmitter {
+    /// particles in the burst
+    pub count: u32,
+    /// time (in ticks) a particle lives
+    pub lifetime: u32,
+    /// middle of the directions particles fly in
+    pub direction: Vec2,
+    /// angle (in radians) around direction, TAU for every direction
+    pub spread: f32,
+    /// lowest and highest starting speed (in px per tick)
+    pub speed: (f32, f32),
+    /// added to the vertical speed every tick
+    pub gravity: f32,
+    /// part of the speed kept every tick
+    pub drag: f32,
+    pub size: f32,
+    pub color_start: Color,
+    /// color at the end of the lifetime, particles fade between the two
+    pub color_end: Color,
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        ParticleEmitter {
+            count: 8,
+            lifetime: 30,
+            direction: Vec2::Y,
+            spread: TAU,
+            speed: (1.0, 3.0),
+            gravity: 0.0,
+            drag: 0.95,
+            size: 8.0,
+            color_start: Color::WHITE,
+            color_end: Color::rgba(1.0, 1.0, 1.0, 0.0),
+        }
+    }
+}
+
+impl ParticleEmitter {
+    /// sparkles flying up from a collected coin
+    pub fn coin() -> Self {
+        ParticleEmitter {
+            count: 12,
+            lifetime: 30,
+            spread: PI,
+            speed: (2.0, 5.0),
+            gravity: -0.15,
+            size: 6.0,
+            color_start: Color::YELLOW,
+            color_end: Color::rgba(1.0, 0.6, 0.0, 0.0),
+            ..default()
+        }
+    }
+
+    /// ring around a door which just got unlocked
+    pub fn door() -> Self {
+        ParticleEmitter {
+            count: 24,
+            lifetime: 40,
+            speed: (3.0, 4.0),
+            drag: 0.92,
+            size: 10.0,
+            color_start: Color::ORANGE,
+            color_end: Color::rgba(1.0, 1.0, 1.0, 0.0),
+            ..default()
+        }
+    }
+
+    /// dust thrown away from a surface, more of it for harder landings
+    pub fn landing(normal: Vec2, strength: f32) -> Self {
+        ParticleEmitter {
+            count: (strength * 10.0).round() as u32,
+            lifetime: 24,
+            direction: normal,
+            spread: PI,
+            speed: (1.0, 4.0),
+            drag: 0.9,
+            color_start: Color::rgb(0.7, 0.7, 0.7),
+            color_end: Color::rgba(0.7, 0.7, 0.7, 0.0),
+            ..default()
+        }
+    }
+
+    /// pieces of the player falling apart
+    pub fn death() -> Self {
+        ParticleEmitter {
+            count: 30,
+            lifetime: 45,
+            speed: (2.0, 7.0),
+            gravity: -0.3,
+            drag: 0.97,
+            size: 12.0,
+            color_start: Color::WHITE,
+            color_end: Color::rgba(1.0, 0.2, 0.2, 0.0),
+            ..default()
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Particle {
+    pub velocity: Vec2,
+    pub age: u32,
+    pub lifetime: u32,
+    pub gravity: f32,
+    pub drag: f32,
+    pub color_start: Color,
+    pub color_end: Color,
+}
+
+// ==== FUNCTIONS ====
+
+/// Spawn an emitter at the given position, drawn in front of the level
+pub fn spawn_particles(commands: &mut Commands, emitter: ParticleEmitter, position: Vec2) {
+    commands.spawn((
+        emitter,
+        TransformBundle::from_transform(Transform::from_xyz(position.x, position.y, 0.5)),
+    ));
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let (a, b) = (a.as_rgba_f32(), b.as_rgba_f32());
+    Color::rgba(
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+        a[3] + (b[3] - a[3]) * t,
+    )
+}
+
+// ==== SYSTEMS ====
+
+/// Replace every new emitter with its burst
+pub fn emit_particles(
+    mut commands: Commands,
+    emitter_query: Query<(Entity, &ParticleEmitter, &Transform), Added<ParticleEmitter>>,
+) {
+    for (entity, emitter, transform) in emitter_query.iter() {
+        let base = emitter.direction.y.atan2(emitter.direction.x);
+        for _ in 0..emitter.count {
+            let angle = base + (random::<f32>() - 0.5) * emitter.spread;
+            let speed = emitter.speed.0 + random::<f32>() * (emitter.speed.1 - emitter.speed.0);
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: emitter.color_start,
+                        custom_size: Some(Vec2::splat(emitter.size)),
+                        ..default()
+                    },
+                    transform: *transform,
+                    ..default()
+                },
+                Particle {
+                    velocity: Vec2::from_angle(angle) * speed,
+                    age: 0,
+                    lifetime: emitter.lifetime,
+                    gravity: emitter.gravity,
+                    drag: emitter.drag,
+                    color_start: emitter.color_start,
+                    color_end: emitter.color_end,
+                },
+            ));
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+pub fn update_particles(
+    mut commands: Commands,
+    mut particle_query: Query<(Entity, &mut Transform, &mut Sprite, &mut Particle)>,
+) {
+    for (entity, mut transform, mut sprite, mut particle) in particle_query.iter_mut() {
+        particle.age += 1;
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation += particle.velocity.extend(0.0);
+        let (gravity, drag) = (particle.gravity, particle.drag);
+        particle.velocity.y += gravity;
+        particle.velocity *= drag;
+        let t = particle.age as f32 / particle.lifetime as f32;
+        sprite.color = lerp_color(particle.color_start, particle.color_end, t);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn despawn_particles(
+    mut commands: Commands,
+    particle_query: Query<Entity, Or<(With<Particle>, With<ParticleEmitter>)>>,
+) {
+    for entity in particle_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
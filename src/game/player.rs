@@ -1,9 +1,8 @@
-use super::{collide, Acceleration, CollisionSides, GravityCounter, JumpLock, objects::obstacle::ObstacleComponent, Size, Speed};
-use crate::{data::mymath::reduction, AppState, SimulationState};
+use super::{collide, ImpactEvent, Ability, AirDash, DoubleJump, GroundPound, PLAYER_DASH_SPEED, PLAYER_DASH_TIME, PLAYER_POUND_SPEED, Acceleration, CollisionSides, GravityCounter, JumpLock, JumpTimers, objects::{obstacle::ObstacleComponent, switch::Inactive}, Size, Speed};
+use crate::{data::{mymath::{ms_to_ticks, reduction}, InputAction, InputBindings, LevelBounds, LevelSpawn, STICK_DEADZONE}, AppState, SimulationState};
 use bevy::{
-    audio::Volume, prelude::*, sprite::{MaterialMesh2dBundle, Mesh2dHandle}
+    prelude::*, sprite::{MaterialMesh2dBundle, Mesh2dHandle}
 };
-use rand::prelude::*;
 
 
 
@@ -15,6 +14,14 @@ pub const PLAYER_JUMP_STRENGTH: f32 = 7.0;
 pub const PLAYER_JUMP_TIME: u32 = 20;
 pub const PLAYER_MASS: f32 = 1.0;
 pub const VOLUME_DETERMINATION_BASE: f32 = 20.0;
+pub const PLAYER_WALL_SLIDE_SPEED: f32 = 2.0;
+pub const PLAYER_WALL_JUMP_KICK: f32 = 8.0;
+/// default time (in ms) the player can still jump after walking off a ledge
+pub const PLAYER_COYOTE_TIME: u32 = 100;
+/// default time (in ms) a jump press is remembered before landing
+pub const PLAYER_JUMP_BUFFER: u32 = 100;
+/// how far (in px) below the level's bounds the player dies
+pub const PLAYER_DEATH_DEPTH: f32 = 100.0;
 
 // ==== PLUGIN ====
 
@@ -25,19 +32,22 @@ impl Plugin for PlayerPlugin {
         app
         // Event
         .add_event::<PlayerInput>()
+        .add_event::<PlayerDeathEvent>()
+        // Resources
+        .init_resource::<PhysicsConfig>()
             // On Enter
             .add_systems(OnEnter(AppState::Game), spawn_player)
             // Update
             .add_systems(
                 Update,
-                (handle_player_keyboard)
+                (handle_player_keyboard,handle_player_gamepad)
                 .run_if(in_state(AppState::Game))
                 .run_if(in_state(SimulationState::Running)),
             )
             // FixedUpdate
             .add_systems(
                 FixedUpdate,
-                (handle_player_input,(update_player_physics,handle_player_obstacle_collision).chain())
+                (handle_player_input,update_player_physics,handle_player_obstacle_collision,kill_fallen_player).chain()
                     .run_if(in_state(AppState::Game))
                     .run_if(in_state(SimulationState::Running)),
             )
@@ -45,6 +55,35 @@ impl Plugin for PlayerPlugin {
             .add_systems(OnExit(AppState::Game), despawn_player);
     }
 }
+// ==== RESOURCES ====
+
+/// Optional player abilities and jump timings, set per run from its info file
+#[derive(Resource)]
+pub struct PhysicsConfig {
+    /// fall slowly while pressed against a wall
+    pub wall_slide: bool,
+    /// jump off walls while in the air
+    pub wall_jump: bool,
+    /// time (in ms) the player can still jump after leaving the ground
+    pub coyote_time: u32,
+    /// time (in ms) a jump press is remembered while it can't be performed
+    pub jump_buffer: u32,
+    /// abilities unlocked for every level of the run, with their charges
+    pub abilities: Vec<(Ability, u32)>,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        PhysicsConfig {
+            wall_slide: false,
+            wall_jump: false,
+            coyote_time: PLAYER_COYOTE_TIME,
+            jump_buffer: PLAYER_JUMP_BUFFER,
+            abilities: Vec::new(),
+        }
+    }
+}
+
 // ==== SYSTEMS =====
 // === SPAWNING AND BASE ====
 
@@ -52,13 +91,42 @@ impl Plugin for PlayerPlugin {
 pub struct PlayerComponent;
 
 #[derive(Event)]
-pub struct PlayerInput(PlayerAction);
+pub struct PlayerInput(pub PlayerAction);
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum PlayerAction {
     MoveLeft,
     MoveRight,
     JumpStart,
-    JumpEnd
+    JumpEnd,
+    Dash,
+    GroundPound,
+}
+
+impl PlayerAction {
+    /// name used in replay files
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlayerAction::MoveLeft => "MOVE_LEFT",
+            PlayerAction::MoveRight => "MOVE_RIGHT",
+            PlayerAction::JumpStart => "JUMP_START",
+            PlayerAction::JumpEnd => "JUMP_END",
+            PlayerAction::Dash => "DASH",
+            PlayerAction::GroundPound => "GROUND_POUND",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PlayerAction> {
+        match name {
+            "MOVE_LEFT" => Some(PlayerAction::MoveLeft),
+            "MOVE_RIGHT" => Some(PlayerAction::MoveRight),
+            "JUMP_START" => Some(PlayerAction::JumpStart),
+            "JUMP_END" => Some(PlayerAction::JumpEnd),
+            "DASH" => Some(PlayerAction::Dash),
+            "GROUND_POUND" => Some(PlayerAction::GroundPound),
+            _ => None,
+        }
+    }
 }
 
 /// Spawn player
@@ -74,13 +142,14 @@ pub fn spawn_player(
             transform: Transform::from_xyz(600.0, 300.0, 1.0),
             ..default()
         },
-        PlayerComponent::default(),
+        PlayerComponent,
         Speed::default(),
         Acceleration::default(),
         GravityCounter::default(),
-        Size{0:Vec2::new(50.0,50.0)},
+        Size(Vec2::new(50.0,50.0)),
         CollisionSides::default(),
-        JumpLock::default()
+        JumpLock::default(),
+        JumpTimers::default()
     ));
 }
 
@@ -93,41 +162,86 @@ pub fn despawn_player(mut commands: Commands, player_query: Query<Entity, With<P
 
 // ==== Movement ====
 
+/// Steps 1 to 8 of the physics: gravity, acceleration and slowing down, before abilities override the speed.<br>
+/// The reach analysis moves its simulated player with it as well.
+pub fn apply_player_physics(
+    speed: &mut Vec3,
+    gravity: &mut u32,
+    acceleration: Vec3,
+    sides: [bool; 4],
+    wall_slide: bool,
+    dashing: bool,
+) {
+    // 1. apply gravity unless on the ground (collision from below), counteracted (gravity timer for jumping) or dashing
+    if !(sides[0] || *gravity>0 || dashing){
+        speed.y -= PLAYER_MASS;
+    }
+    // Step 2 is handled withing system handle_player_input
+    // Step 3 is handled in fn handle_player_input
+    // 4. decrease gravty counteraction time by 1 if >0
+    if *gravity > 0 {
+        *gravity -= 1;
+    }
+    // 5. apply acceleration to speed on x axis while limiting value withing borders
+    if (acceleration.x<0.0 && !sides[2]) || (acceleration.x>0.0 && !sides[3]) {
+        speed.x += acceleration.x;
+    }
+    // 6. apply acceleration to speed on y axis
+    speed.y += acceleration.y;
+    // 6.5 slide down walls the player is pushing against
+    let pushing_wall = (acceleration.x<0.0 && sides[2]) || (acceleration.x>0.0 && sides[3]);
+    if wall_slide && pushing_wall && !sides[0] && speed.y < -PLAYER_WALL_SLIDE_SPEED {
+        speed.y = -PLAYER_WALL_SLIDE_SPEED;
+    }
+    // 8. clamp speed & reduce speed on x axis
+    match sides[0] { // further reduction if on the ground
+        true => {speed.x = reduction(speed.x, PLAYER_DECELERATION_RATE*PLAYER_FRICTION_RATE, 0.5);},
+        false => {speed.x = reduction(speed.x, PLAYER_DECELERATION_RATE, 0.5);}
+    };
+    //speed.y = reduction(speed.y, PLAYER_DECELERATION_RATE, 0.5);
+}
+
 /// Perform all physics calculations
 pub fn update_player_physics(
     mut player_query: Query<(&mut Transform, Entity), With<PlayerComponent>>,
     mut player_spd: Query<(&mut Speed,&mut CollisionSides,&mut GravityCounter), With<PlayerComponent>>,
     mut player_acc: Query<&mut Acceleration, With<PlayerComponent>>,
+    mut player_abilities: Query<(Option<&mut AirDash>, Option<&mut GroundPound>), With<PlayerComponent>>,
+    physics_config: Res<PhysicsConfig>,
 ) {
     if let Ok((mut player_speed, mut player_sides, mut player_gravity)) = player_spd.get_single_mut() {
-        // 1. apply gravity unless on the ground (collision from below) or counteracted (gravity timer for jumping)
-        if !(player_sides.0[0] || player_gravity.0>0){
-            player_speed.0.y -= PLAYER_MASS;
+        let (mut dash, mut pound) = player_abilities.get_single_mut().unwrap_or((None, None));
+        let dashing = dash.as_ref().is_some_and(|d| d.counter > 0);
+        let acceleration = player_acc.get_single().map_or(Vec3::ZERO, |a| a.0);
+        apply_player_physics(
+            &mut player_speed.0,
+            &mut player_gravity.0,
+            acceleration,
+            player_sides.0,
+            physics_config.wall_slide,
+            dashing,
+        );
+        // 7. reset acceleration
+        if let Ok(mut player_acceleration) = player_acc.get_single_mut() {
+            player_acceleration.0 = Vec3::ZERO;
         }
-        // Step 2 is handled withing system handle_player_input
-        // Step 3 is handled in fn handle_player_input
-        // 4. decrease gravty counteraction time by 1 if >0
-        if player_gravity.0 > 0 {
-            player_gravity.0 -= 1;
+
+        // 8.5 abilities overriding the movement
+        if let Some(dash) = dash.as_mut() {
+            if dash.counter > 0 {
+                player_speed.0.x = dash.facing*PLAYER_DASH_SPEED;
+                player_speed.0.y = 0.0;
+                dash.counter -= 1;
+            }
         }
-        //
-        if let Ok(mut player_acceleration) = player_acc.get_single_mut() {
-            // 5. apply acceleration to speed on x axis while limiting value withing borders
-            if (player_acceleration.0.x<0.0 && !player_sides.0[2]) || (player_acceleration.0.x>0.0 && !player_sides.0[3]) {
-                player_speed.0.x += player_acceleration.0.x;
+        if let Some(pound) = pound.as_mut() {
+            if pound.active && player_sides.0[0] {
+                pound.active = false;
+            } else if pound.active {
+                player_speed.0.x = 0.0;
+                player_speed.0.y = -PLAYER_POUND_SPEED;
             }
-            // 6. apply acceleration to speed on y axis
-            player_speed.0.y += player_acceleration.0.y;
-            // 7. reset acceleration
-            player_acceleration.0.x = 0.0;
-            player_acceleration.0.y = 0.0;
         }
-        // 8. clamp speed & reduce speed on x axis
-        match player_sides.0[0] { // further reduction if on the ground
-            true => {player_speed.0.x = reduction(player_speed.0.x, PLAYER_DECELERATION_RATE*PLAYER_FRICTION_RATE, 0.5);},
-            false => {player_speed.0.x = reduction(player_speed.0.x, PLAYER_DECELERATION_RATE, 0.5);}
-        };
-        //player_speed.0.y = reduction(player_speed.0.y, PLAYER_DECELERATION_RATE, 0.5);
         
         // 9. restart gravity (set counteraction time to 0) if jump is cancelled 
 
@@ -148,24 +262,64 @@ pub fn update_player_physics(
 /// Apply player input to acceleration
 pub fn handle_player_keyboard(
     mut event_writer: EventWriter<PlayerInput>,
-    keyboard_input: Res<ButtonInput<KeyCode>>
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<InputBindings>,
 ) {
     // 2. apply acceleration on x axis by controller
     // Accelerate left
-    if keyboard_input.pressed(KeyCode::KeyA) {
+    if bindings.pressed(InputAction::MoveLeft, &keyboard_input) {
         event_writer.send(PlayerInput(PlayerAction::MoveLeft));
     }
     // Accelerate right
-    if keyboard_input.pressed(KeyCode::KeyD) {
+    if bindings.pressed(InputAction::MoveRight, &keyboard_input) {
         event_writer.send(PlayerInput(PlayerAction::MoveRight));
     }
     // 3. apply jump-related acceleration on y axis & grant counteraction time
-    if keyboard_input.just_pressed(KeyCode::Space) {
+    if bindings.just_pressed(InputAction::Jump, &keyboard_input) {
+        event_writer.send(PlayerInput(PlayerAction::JumpStart));
+    }
+    if bindings.just_released(InputAction::Jump, &keyboard_input) {
+        event_writer.send(PlayerInput(PlayerAction::JumpEnd));
+    }
+    // abilities
+    if bindings.just_pressed(InputAction::Dash, &keyboard_input) {
+        event_writer.send(PlayerInput(PlayerAction::Dash));
+    }
+    if bindings.just_pressed(InputAction::GroundPound, &keyboard_input) {
+        event_writer.send(PlayerInput(PlayerAction::GroundPound));
+    }
+}
+
+/// Same as handle_player_keyboard, for every connected gamepad
+pub fn handle_player_gamepad(
+    mut event_writer: EventWriter<PlayerInput>,
+    gamepads: Res<Gamepads>,
+    pad_input: Res<ButtonInput<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    bindings: Res<InputBindings>,
+) {
+    // left stick moves the player as well as bound buttons
+    let stick = gamepads.iter()
+        .filter_map(|pad| axes.get(GamepadAxis::new(pad, GamepadAxisType::LeftStickX)))
+        .fold(0.0, |a, b| if b.abs() > f32::abs(a) {b} else {a});
+    if stick < -STICK_DEADZONE || bindings.pad_pressed(InputAction::MoveLeft, &gamepads, &pad_input) {
+        event_writer.send(PlayerInput(PlayerAction::MoveLeft));
+    }
+    if stick > STICK_DEADZONE || bindings.pad_pressed(InputAction::MoveRight, &gamepads, &pad_input) {
+        event_writer.send(PlayerInput(PlayerAction::MoveRight));
+    }
+    if bindings.pad_just_pressed(InputAction::Jump, &gamepads, &pad_input) {
         event_writer.send(PlayerInput(PlayerAction::JumpStart));
     }
-    if keyboard_input.just_released(KeyCode::Space) {
+    if bindings.pad_just_released(InputAction::Jump, &gamepads, &pad_input) {
         event_writer.send(PlayerInput(PlayerAction::JumpEnd));
     }
+    if bindings.pad_just_pressed(InputAction::Dash, &gamepads, &pad_input) {
+        event_writer.send(PlayerInput(PlayerAction::Dash));
+    }
+    if bindings.pad_just_pressed(InputAction::GroundPound, &gamepads, &pad_input) {
+        event_writer.send(PlayerInput(PlayerAction::GroundPound));
+    }
 }
 
 // pub fn no_grav(
@@ -175,45 +329,135 @@ pub fn handle_player_keyboard(
 
 // }
 
+#[allow(clippy::type_complexity)]
 pub fn handle_player_input (
     mut event_reader: EventReader<PlayerInput>,
-    mut player_attrib: Query<(&mut Acceleration,&mut JumpLock,&mut GravityCounter), With<PlayerComponent>>,
+    mut player_attrib: Query<(&mut Acceleration,&mut Speed,&mut JumpLock,&mut GravityCounter,&mut JumpTimers,&CollisionSides), With<PlayerComponent>>,
+    mut player_abilities: Query<(Option<&mut DoubleJump>, Option<&mut AirDash>, Option<&mut GroundPound>), With<PlayerComponent>>,
+    player_body: Query<(&Transform, &Size), With<PlayerComponent>>,
+    obstacle_query: Query<(&Transform, &Size), (With<ObstacleComponent>, Without<PlayerComponent>, Without<Inactive>)>,
+    physics_config: Res<PhysicsConfig>,
+    fixed_time: Res<Time<Fixed>>,
 ) {
-    if let Ok((mut player_acc,mut player_jumplock,mut player_grav)) = player_attrib.get_single_mut() {
+    if let Ok((mut player_acc,mut player_speed,mut player_jumplock,mut player_grav,mut timers,player_sides)) = player_attrib.get_single_mut() {
+        let tick = fixed_time.timestep().as_secs_f32();
+        let coyote_ticks = ms_to_ticks(physics_config.coyote_time, tick);
+        let buffer_ticks = ms_to_ticks(physics_config.jump_buffer, tick);
+        let (mut double_jump, mut dash, mut pound) = player_abilities.get_single_mut().unwrap_or((None, None, None));
+        // count time since the player last stood on the ground
+        if player_sides.0[0] {
+            timers.since_ground = 0;
+            // landing restores ability charges
+            if let Some(double_jump) = double_jump.as_mut() {double_jump.charges = double_jump.max;}
+            if let Some(dash) = dash.as_mut() {dash.charges = dash.max;}
+        } else {
+            timers.since_ground = timers.since_ground.saturating_add(1);
+        }
+        // jumping is allowed on the ground and shortly after leaving it
+        let can_jump = !player_jumplock.0 && timers.since_ground <= coyote_ticks;
+        // a press this close to the ground gets buffered instead of using up the double jump
+        let landing = player_body.get_single().is_ok_and(|(transform, size)| lands_within(
+            transform.translation.xy(), size.0, player_speed.0, buffer_ticks,
+            obstacle_query.iter().map(|(t, s)| (t.translation.xy(), s.0)),
+        ));
         for event in event_reader.read() {
             match event.0 {
                 PlayerAction::MoveLeft => {
                     player_acc.0.x -= PLAYER_ACCELERATION;
+                    if let Some(dash) = dash.as_mut() {dash.facing = -1.0;}
                 },
                 PlayerAction::MoveRight => {
                     player_acc.0.x += PLAYER_ACCELERATION;
+                    if let Some(dash) = dash.as_mut() {dash.facing = 1.0;}
                 },
                 PlayerAction::JumpStart => {
-                if !player_jumplock.0 {
-                    // apply jump acceleration
-                    player_acc.0.y += PLAYER_JUMP_STRENGTH;
-                    // lock jump (it gets unlocked at collision with ground, within fn player_obstacle_collision)
-                    player_jumplock.0 = true;
-                    // set gravity to jump time
-                    player_grav.0 = PLAYER_JUMP_TIME;
-                };
+                    if can_jump {
+                        jump(&mut player_acc, &mut player_jumplock, &mut player_grav);
+                    } else if physics_config.wall_jump && !player_sides.0[0] && (player_sides.0[2] || player_sides.0[3]) {
+                        // kick away from the wall, ignoring any fall speed
+                        let direction = if player_sides.0[2] {1.0} else {-1.0};
+                        if player_speed.0.y<0.0 {player_speed.0.y=0.0};
+                        player_acc.0.x += direction*PLAYER_WALL_JUMP_KICK;
+                        player_acc.0.y += PLAYER_JUMP_STRENGTH;
+                        player_grav.0 = PLAYER_JUMP_TIME;
+                    } else if let Some(double_jump) = double_jump.as_mut().filter(|d| d.charges > 0 && !player_sides.0[0] && !landing) {
+                        // jump again in the air, starting from zero vertical speed
+                        double_jump.charges -= 1;
+                        player_speed.0.y = 0.0;
+                        jump(&mut player_acc, &mut player_jumplock, &mut player_grav);
+                    } else {
+                        // remember the press, it may become a jump after landing
+                        timers.buffered = Some(0);
+                        timers.released = false;
+                    };
                 },
                 PlayerAction::JumpEnd => {
                     player_grav.0 = 0;
+                    timers.released = true;
+                },
+                PlayerAction::Dash => {
+                    if let Some(dash) = dash.as_mut().filter(|d| d.charges > 0 && d.counter == 0 && !player_sides.0[0]) {
+                        dash.charges -= 1;
+                        dash.counter = PLAYER_DASH_TIME;
+                        player_grav.0 = 0;
+                        if let Some(pound) = pound.as_mut() {pound.active = false;}
+                    }
+                },
+                PlayerAction::GroundPound => {
+                    if let Some(pound) = pound.as_mut().filter(|p| !p.active && !player_sides.0[0]) {
+                        pound.active = true;
+                        player_grav.0 = 0;
+                        if let Some(dash) = dash.as_mut() {dash.counter = 0;}
+                    }
                 },
             }
-        }   
+        }
+        // perform a buffered jump as soon as it's possible
+        if let Some(age) = timers.buffered {
+            if can_jump && !player_jumplock.0 {
+                jump(&mut player_acc, &mut player_jumplock, &mut player_grav);
+                // button already let go, make it a short hop
+                if timers.released {player_grav.0 = 0;}
+                timers.buffered = None;
+            } else if age >= buffer_ticks {
+                timers.buffered = None;
+            } else {
+                timers.buffered = Some(age+1);
+            }
+        }
     }
 
 }
 
-/// 
+/// Whether a falling player reaches the top of an obstacle below within the given ticks
+fn lands_within(position: Vec2, size: Vec2, speed: Vec3, ticks: u32, mut obstacles: impl Iterator<Item = (Vec2, Vec2)>) -> bool {
+    if speed.y > 0.0 {return false;}
+    // distance fallen in that time, speeding up with gravity
+    let depth: f32 = (1..=ticks).map(|t| t as f32 * PLAYER_MASS - speed.y).sum();
+    let bottom = position.y - size.y / 2.0;
+    obstacles.any(|(pos, obstacle_size)| {
+        let top = pos.y + obstacle_size.y / 2.0;
+        (pos.x - position.x).abs() < (size.x + obstacle_size.x) / 2.0 && top <= bottom + 1.0 && bottom - top <= depth
+    })
+}
+
+/// Apply jump acceleration, lock jumping and counteract gravity for the jump time
+fn jump(acceleration: &mut Acceleration, jump_lock: &mut JumpLock, gravity_counter: &mut GravityCounter) {
+    // apply jump acceleration
+    acceleration.0.y += PLAYER_JUMP_STRENGTH;
+    // lock jump (it gets unlocked at collision with ground, within fn player_obstacle_collision)
+    jump_lock.0 = true;
+    // set gravity to jump time
+    gravity_counter.0 = PLAYER_JUMP_TIME;
+}
+
+/// Resolve collisions between the player and every active obstacle
+#[allow(clippy::type_complexity)]
 pub fn handle_player_obstacle_collision(
     mut player_query: Query<(&mut Transform, &mut Speed,& Size,&mut CollisionSides,
          &mut JumpLock, &mut GravityCounter), With<PlayerComponent>>,
-    obstacle_query: Query<(&Transform, &Size), (With<ObstacleComponent>, Without<PlayerComponent>)>,
-    mut commands: Commands,
-    asset_server: Res<AssetServer>
+    obstacle_query: Query<(&Transform, &Size), (With<ObstacleComponent>, Without<PlayerComponent>, Without<Inactive>)>,
+    mut impacts: EventWriter<ImpactEvent>,
 ) {
     if let Ok((mut pt,mut psd, ps,mut cs,
          mut pj,mut gc)) = player_query.get_single_mut() {
@@ -222,14 +466,15 @@ pub fn handle_player_obstacle_collision(
             player_obstacle_collision(
                 &mut pt,&mut psd, ps, 
                 &mut cs, 
-                &mut pj, &mut gc, ot, os, &mut commands, &asset_server
+                &mut pj, &mut gc, ot, os, &mut impacts
             )
         }
     }
 }
 }
 
-///
+/// Push the player out of an obstacle on the side with the smallest overlap
+#[allow(clippy::too_many_arguments)]
 pub fn player_obstacle_collision(
     player_transform: &mut Transform,
     player_speed: &mut Speed,
@@ -239,20 +484,58 @@ pub fn player_obstacle_collision(
     gravity_counter: &mut GravityCounter,
     obstacle_transform: &Transform,
     obstacle_size: &Size,
-    mut commands: &mut Commands,
-    asset_server: &Res<AssetServer>
+    impacts: &mut EventWriter<ImpactEvent>,
 ) {
+    let strength = player_speed.0.y.abs()/VOLUME_DETERMINATION_BASE;
+    let mk = push_out_of_obstacle(
+        &mut player_transform.translation, &mut player_speed.0, player_size.0,
+        obstacle_transform.translation.xy(), obstacle_size.0,
+    );
+    match mk {
+        0 => {
+            // Collision with a floor
+            impacts.send(ImpactEvent {
+                position: player_transform.translation.xy() - Vec2::new(0.0, player_size.0.y/2.0),
+                normal: Vec2::Y,
+                strength
+            });
+            jump_lock.0 = false;
+        },
+        1 => {
+            // Collision with the ceiling
+            impacts.send(ImpactEvent {
+                position: player_transform.translation.xy() + Vec2::new(0.0, player_size.0.y/2.0),
+                normal: Vec2::NEG_Y,
+                strength
+            });
+            gravity_counter.0 = 0;
+        },
+        _ => {}
+    }
+    collision_sides.0[mk]=true;
+    //println!("{:?}",collision_sides.0);
+}
+
+/// Move the player out of an obstacle and stop it moving into it, returns the side that was hit:<br>
+/// 0 floor, 1 ceiling, 2 obstacle on the left, 3 obstacle on the right
+pub fn push_out_of_obstacle(
+    player_position: &mut Vec3,
+    player_speed: &mut Vec3,
+    player_size: Vec2,
+    obstacle_position: Vec2,
+    obstacle_size: Vec2,
+) -> usize {
     let mut mk = 32;
     let mut _mv = f32::MAX;
     let mut d = (0.0,0.0,0.0,0.0);
     // distance between player's bottom and obstacle's top
-    d.0 = (player_transform.translation.y-player_size.0.y/2.0-(obstacle_transform.translation.y+obstacle_size.0.y/2.0)).abs();
+    d.0 = (player_position.y-player_size.y/2.0-(obstacle_position.y+obstacle_size.y/2.0)).abs();
     // distance between player's top and obstacle's bottom
-    d.1 = (player_transform.translation.y+player_size.0.y/2.0-(obstacle_transform.translation.y-obstacle_size.0.y/2.0)).abs();
+    d.1 = (player_position.y+player_size.y/2.0-(obstacle_position.y-obstacle_size.y/2.0)).abs();
     // distance between player's left and obstacle's right
-    d.2 = (player_transform.translation.x-player_size.0.x/2.0-(obstacle_transform.translation.x+obstacle_size.0.x/2.0)).abs();
+    d.2 = (player_position.x-player_size.x/2.0-(obstacle_position.x+obstacle_size.x/2.0)).abs();
     // distance between player's right and obstacle's left
-    d.3 = (player_transform.translation.x+player_size.0.x/2.0-(obstacle_transform.translation.x-obstacle_size.0.x/2.0)).abs();
+    d.3 = (player_position.x+player_size.x/2.0-(obstacle_position.x-obstacle_size.x/2.0)).abs();
     //println!("{:?}",d);
     if d.0<_mv {mk=0;_mv=d.0};
     if d.1<_mv {mk=1;_mv=d.1};
@@ -262,59 +545,203 @@ pub fn player_obstacle_collision(
         0 => {
             // Collision with a floor
             //println!("floor");
-            player_transform.translation.y = obstacle_transform.translation.y+player_size.0.y/2.0+obstacle_size.0.y/2.0;
-            play_impact(&mut commands,&asset_server,Volume::new(player_speed.0.y.abs()/VOLUME_DETERMINATION_BASE));
-            jump_lock.0 = false;
-            if player_speed.0.y<0.0 {player_speed.0.y=0.0};
+            player_position.y = obstacle_position.y+player_size.y/2.0+obstacle_size.y/2.0;
+            if player_speed.y<0.0 {player_speed.y=0.0};
         },
         1 => {
             // Collision with the ceiling
             //println!("ceiling");
-            player_transform.translation.y = obstacle_transform.translation.y-player_size.0.y/2.0-obstacle_size.0.y/2.0;
-            play_impact(&mut commands,&asset_server,Volume::new( player_speed.0.y.abs()/VOLUME_DETERMINATION_BASE));
-            if player_speed.0.y>0.0 {player_speed.0.y=0.0};
-            gravity_counter.0 = 0;
+            player_position.y = obstacle_position.y-player_size.y/2.0-obstacle_size.y/2.0;
+            if player_speed.y>0.0 {player_speed.y=0.0};
         },
         2 => {
             // Collision on the left (player on the right of obstacle)
             //println!("left");
-            player_transform.translation.x = obstacle_transform.translation.x+player_size.0.x/2.0+obstacle_size.0.x/2.0;
-            //play_impact(&mut commands,&asset_server,Volume::new(-player_speed.0.x/VOLUME_DETERMINATION_BASE));
-            if player_speed.0.x<0.0 {player_speed.0.x=0.0};
+            player_position.x = obstacle_position.x+player_size.x/2.0+obstacle_size.x/2.0;
+            if player_speed.x<0.0 {player_speed.x=0.0};
         },
         3 => {
             // Collision on the right (player on the left of obstacle)
             //println!("right");
-            player_transform.translation.x = obstacle_transform.translation.x-player_size.0.x/2.0-obstacle_size.0.x/2.0;
-            //play_impact(&mut commands,&asset_server,Volume::new( player_speed.0.x/VOLUME_DETERMINATION_BASE));
-            if player_speed.0.x>0.0 {player_speed.0.x=0.0};
+            player_position.x = obstacle_position.x-player_size.x/2.0-obstacle_size.x/2.0;
+            if player_speed.x>0.0 {player_speed.x=0.0};
         },
         _ => {println!("At player_obstacle_collision, somehow an impossible collision side key was matched...");}
     }
-    collision_sides.0[mk]=true;
-    //println!("{:?}",collision_sides.0);
+    mk
 }
 
-pub fn play_impact(
-    commands: &mut Commands,
-    asset_server: &Res<AssetServer>,
-    volume: Volume
+/// Player fell out of the level
+#[derive(Event)]
+pub struct PlayerDeathEvent {
+    /// where the player left the level
+    pub position: Vec2,
+}
+
+/// Respawn the player at the level's start once they fall below its bounds.<br>
+/// Collected coins stay collected.
+pub fn kill_fallen_player(
+    mut player_query: Query<(&mut Transform, &mut Speed, &mut Acceleration, &mut GravityCounter), With<PlayerComponent>>,
+    level_bounds: Res<LevelBounds>,
+    level_spawn: Res<LevelSpawn>,
+    mut event_writer: EventWriter<PlayerDeathEvent>,
 ) {
-        // let sound = if random::<f32>() > 0.5 {
-        //     asset_server.load("audio/impactGeneric_light_000.ogg")
-        // } else {
-        //     asset_server.load("audio/impactGeneric_light_001.ogg")
-        // };
-        let sound: [&str;5] = [
-            "audio/impactGeneric_light_000.ogg",
-            "audio/impactGeneric_light_001.ogg",
-            "audio/impactGeneric_light_002.ogg",
-            "audio/impactGeneric_light_003.ogg",
-            "audio/impactGeneric_light_004.ogg"
-        ];
-        let sound = sound.choose(&mut rand::thread_rng()).unwrap().to_owned();
-        commands.spawn(AudioBundle {
-            source: asset_server.load(sound),
-            settings: PlaybackSettings::DESPAWN.with_volume(volume),
-        });
-}
\ No newline at end of file
+    let Ok((mut transform, mut speed, mut acceleration, mut gravity)) = player_query.get_single_mut() else {return};
+    if transform.translation.y > level_bounds.0.min.y - PLAYER_DEATH_DEPTH {return;}
+    // the bottom edge of the view, where the player fell out
+    let position = Vec2::new(transform.translation.x, level_bounds.0.min.y);
+    event_writer.send(PlayerDeathEvent { position });
+    println!("[PLAYER] Fell out of the level, respawning");
+    transform.translation.x = level_spawn.0.x;
+    transform.translation.y = level_spawn.0.y;
+    speed.0 = Vec3::ZERO;
+    acceleration.0 = Vec3::ZERO;
+    gravity.0 = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{input::InputPlugin, time::TimeUpdateStrategy};
+
+    use crate::{data::PLAYER_SIZE, game::jump_envelope};
+
+    use super::*;
+
+    /// Player without a level, one fixed tick per update
+    fn player_app(physics_config: PhysicsConfig) -> App {
+        let tick = Duration::from_secs_f64(1.0 / 60.0);
+        let mut app = App::new();
+        app
+            //.
+            .add_plugins((MinimalPlugins, InputPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(tick))
+            .insert_resource(Time::<Fixed>::from_duration(tick))
+            .insert_state(AppState::Game)
+            .init_state::<SimulationState>()
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .init_resource::<InputBindings>()
+            .init_resource::<LevelBounds>()
+            .init_resource::<LevelSpawn>()
+            .add_event::<ImpactEvent>()
+            .insert_resource(physics_config)
+            .add_plugins(PlayerPlugin)
+            //.
+            ;
+        app.update();
+        app
+    }
+
+    /// Player in the air at the left side of a tall wall
+    fn player_at_wall(physics_config: PhysicsConfig) -> App {
+        let mut app = player_app(physics_config);
+        app.world.spawn((ObstacleComponent, Transform::from_xyz(650.0, 500.0, 1.0), Size(Vec2::new(100.0, 1000.0))));
+        let mut player = app.world.query_filtered::<&mut Transform, With<PlayerComponent>>();
+        player.single_mut(&mut app.world).translation = Vec3::new(575.0, 800.0, 1.0);
+        app
+    }
+
+    fn run(app: &mut App, ticks: u32, actions: &[PlayerAction]) {
+        for _ in 0..ticks {
+            for action in actions {
+                app.world.send_event(PlayerInput(*action));
+            }
+            app.update();
+        }
+    }
+
+    fn player_speed(app: &mut App) -> Vec3 {
+        app.world.query_filtered::<&Speed, With<PlayerComponent>>().single(&app.world).0
+    }
+
+    fn player_sides(app: &mut App) -> [bool; 4] {
+        app.world.query_filtered::<&CollisionSides, With<PlayerComponent>>().single(&app.world).0
+    }
+
+    fn walls_on() -> PhysicsConfig {
+        PhysicsConfig { wall_slide: true, wall_jump: true, ..default() }
+    }
+
+    #[test]
+    fn wall_slide_clamps_fall_speed() {
+        let mut app = player_at_wall(walls_on());
+        run(&mut app, 30, &[PlayerAction::MoveRight]);
+        assert!(player_sides(&mut app)[3]);
+        assert_eq!(player_speed(&mut app).y, -PLAYER_WALL_SLIDE_SPEED);
+        // letting go of the wall makes the player fall freely again
+        run(&mut app, 10, &[]);
+        assert!(player_speed(&mut app).y < -PLAYER_WALL_SLIDE_SPEED);
+    }
+
+    #[test]
+    fn wall_jump_kicks_away_from_wall() {
+        let mut app = player_at_wall(walls_on());
+        run(&mut app, 30, &[PlayerAction::MoveRight]);
+        run(&mut app, 1, &[PlayerAction::JumpStart]);
+        let speed = player_speed(&mut app);
+        assert!(speed.x < 0.0, "no kick away from the wall: {}", speed);
+        assert!(speed.y > 0.0, "no jump off the wall: {}", speed);
+    }
+
+    #[test]
+    fn walls_do_nothing_when_disabled() {
+        let mut app = player_at_wall(PhysicsConfig::default());
+        run(&mut app, 30, &[PlayerAction::MoveRight]);
+        assert!(player_sides(&mut app)[3]);
+        assert!(player_speed(&mut app).y < -PLAYER_WALL_SLIDE_SPEED);
+        run(&mut app, 1, &[PlayerAction::JumpStart]);
+        let speed = player_speed(&mut app);
+        assert!(speed.x >= 0.0 && speed.y < 0.0, "jumped off the wall: {}", speed);
+    }
+
+    #[test]
+    fn jump_before_landing_keeps_double_jump() {
+        let mut app = player_app(PhysicsConfig::default());
+        app.world.spawn((ObstacleComponent, Transform::from_xyz(600.0, 100.0, 1.0), Size(Vec2::new(400.0, 100.0))));
+        let mut player = app.world.query_filtered::<(Entity, &mut Transform), With<PlayerComponent>>();
+        let (entity, mut transform) = player.single_mut(&mut app.world);
+        transform.translation = Vec3::new(600.0, 400.0, 1.0);
+        app.world.entity_mut(entity).insert(DoubleJump { charges: 1, max: 1 });
+        // fall until a few ticks above the floor
+        let mut ticks = 0;
+        while app.world.query_filtered::<&Transform, With<PlayerComponent>>().single(&app.world).translation.y > 190.0 {
+            run(&mut app, 1, &[]);
+            ticks += 1;
+            assert!(ticks < 100, "the player didn't fall");
+        }
+        run(&mut app, 1, &[PlayerAction::JumpStart]);
+        let charges = |app: &mut App| app.world.query::<&DoubleJump>().single(&app.world).charges;
+        assert_eq!(charges(&mut app), 1);
+        // the buffered press jumps off the floor
+        run(&mut app, 6, &[]);
+        assert!(player_speed(&mut app).y > 0.0);
+        assert_eq!(charges(&mut app), 1);
+    }
+
+    #[test]
+    fn jump_matches_reach_envelope() {
+        let mut app = player_app(PhysicsConfig::default());
+        app.world.spawn((ObstacleComponent, Transform::from_xyz(0.0, 100.0, 1.0), Size(Vec2::new(1.0e6, 2.0))));
+        let mut player = app.world.query_filtered::<&mut Transform, With<PlayerComponent>>();
+        player.single_mut(&mut app.world).translation = Vec3::new(200.0, 101.0 + PLAYER_SIZE / 2.0, 1.0);
+        let position = |app: &mut App| app.world.query_filtered::<&Transform, With<PlayerComponent>>().single(&app.world).translation;
+        // settle on the floor
+        run(&mut app, 1, &[]);
+        assert!(player_sides(&mut app)[0]);
+        let start = position(&mut app);
+        run(&mut app, 1, &[PlayerAction::JumpStart, PlayerAction::MoveRight]);
+        let mut height: f32 = 0.0;
+        for _ in 0..600 {
+            height = height.max(position(&mut app).y - start.y);
+            if player_sides(&mut app)[0] {break;}
+            run(&mut app, 1, &[PlayerAction::MoveRight]);
+        }
+        let (envelope_height, envelope_length) = jump_envelope();
+        // same ticks, only rounding differs with the position
+        assert!((height - envelope_height).abs() < 0.01, "{} high, the envelope says {}", height, envelope_height);
+        let length = position(&mut app).x - start.x;
+        assert!((length - envelope_length).abs() < 0.01, "{} far, the envelope says {}", length, envelope_length);
+    }
+}
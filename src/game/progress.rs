@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+
+use crate::{AppState, SimulationState};
+
+use super::{handle_player_input, kill_fallen_player, LoadRunEvent, PlayerDeathEvent};
+
+// ==== PLUGIN ====
+
+/// Time, splits and coins of the run being played, reported with LevelCompleteEvent
+pub struct ProgressPlugin;
+
+impl Plugin for ProgressPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //.
+        .init_resource::<RunProgress>()
+        .add_event::<LevelCompleteEvent>()
+        .add_systems(FixedUpdate, reset_run_progress
+            .before(handle_player_input)
+            .run_if(in_state(AppState::Game)))
+        // counted in ticks, so the time doesn't depend on the frame rate
+        .add_systems(FixedUpdate, (count_deaths, tick_run_progress).chain()
+            .after(kill_fallen_player)
+            .run_if(in_state(AppState::Game))
+            .run_if(in_state(SimulationState::Running)))
+        //.
+        ;
+    }
+}
+
+// ==== RESOURCES & EVENTS ====
+
+/// Reset whenever a run is (re)started
+#[derive(Resource, Default)]
+pub struct RunProgress {
+    /// time (in seconds) the run was played, pauses don't count
+    pub time: f32,
+    /// run time at the end of each finished level
+    pub splits: Vec<f32>,
+    /// coins collected in finished levels
+    pub coins: usize,
+    pub deaths: usize,
+    /// deaths since the current level was started
+    pub level_deaths: usize,
+}
+
+impl RunProgress {
+    /// time spent in the current level
+    pub fn level_time(&self) -> f32 {
+        self.time - self.splits.last().unwrap_or(&0.0)
+    }
+}
+
+/// Player walked through a door, sent by door_player_collide
+#[derive(Event)]
+pub struct LevelCompleteEvent {
+    /// path of the run
+    pub run: String,
+    pub level: usize,
+    /// whether it was the run's last level
+    pub last: bool,
+    pub level_time: f32,
+    pub level_coins: usize,
+    pub level_medal: Option<Medal>,
+    /// totals of the run so far, including this level
+    pub run_time: f32,
+    pub run_coins: usize,
+    pub splits: Vec<f32>,
+    /// only rated after the last level
+    pub run_medal: Option<Medal>,
+}
+
+// ==== MEDALS ====
+
+/// Rating of a finished run or level, better medals compare greater
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Medal {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+impl Medal {
+    /// name used in run info and save files
+    pub fn from_name(name: &str) -> Option<Medal> {
+        match name {
+            "BRONZE" => Some(Medal::Bronze),
+            "SILVER" => Some(Medal::Silver),
+            "GOLD" => Some(Medal::Gold),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Medal::Bronze => "BRONZE",
+            Medal::Silver => "SILVER",
+            Medal::Gold => "GOLD",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Medal::Bronze => "Bronze",
+            Medal::Silver => "Silver",
+            Medal::Gold => "Gold",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Medal::Bronze => Color::rgb(0.8, 0.5, 0.2),
+            Medal::Silver => Color::rgb(0.75, 0.75, 0.8),
+            Medal::Gold => Color::rgb(1.0, 0.84, 0.0),
+        }
+    }
+}
+
+/// Limits a finish has to be within to earn the medal
+#[derive(Clone, PartialEq, Debug)]
+pub struct MedalThreshold {
+    pub medal: Medal,
+    /// slowest time (in seconds)
+    pub time: f32,
+    /// fewest coins collected
+    pub coins: usize,
+    /// most deaths
+    pub deaths: usize,
+}
+
+impl MedalThreshold {
+    /// `<GOLD|SILVER|BRONZE> <seconds> <coins> <deaths>`
+    pub fn parse(segs: &[&str]) -> Option<MedalThreshold> {
+        Some(MedalThreshold {
+            medal: Medal::from_name(segs.first()?)?,
+            time: segs.get(1)?.parse().ok()?,
+            coins: segs.get(2)?.parse().ok()?,
+            deaths: segs.get(3)?.parse().ok()?,
+        })
+    }
+
+    pub fn met(&self, time: f32, coins: usize, deaths: usize) -> bool {
+        time <= self.time && coins >= self.coins && deaths <= self.deaths
+    }
+}
+
+/// best medal whose limits were all met, none without thresholds
+pub fn rate_finish(thresholds: &[MedalThreshold], time: f32, coins: usize, deaths: usize) -> Option<Medal> {
+    thresholds.iter()
+        .filter(|t| t.met(time, coins, deaths))
+        .map(|t| t.medal)
+        .max()
+}
+
+// ==== SYSTEMS ====
+
+pub fn reset_run_progress(
+    mut event_reader: EventReader<LoadRunEvent>,
+    mut progress: ResMut<RunProgress>,
+) {
+    if event_reader.read().last().is_some() {
+        *progress = RunProgress::default();
+    }
+}
+
+pub fn count_deaths(
+    mut event_reader: EventReader<PlayerDeathEvent>,
+    mut progress: ResMut<RunProgress>,
+) {
+    for _ in event_reader.read() {
+        progress.deaths += 1;
+        progress.level_deaths += 1;
+    }
+}
+
+pub fn tick_run_progress(mut progress: ResMut<RunProgress>, time: Res<Time>) {
+    progress.time += time.delta_seconds();
+}
@@ -0,0 +1,351 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::{
+    camera::{PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH},
+    data::{format_pos, legacy_pos, level_paths, mymath::ms_to_ticks, read_level_objects, LevelObject, COIN_SIZE, PLAYER_SIZE},
+};
+
+use super::{
+    apply_player_physics, collide, push_out_of_obstacle, PLAYER_ACCELERATION, PLAYER_COYOTE_TIME, PLAYER_DEATH_DEPTH,
+    PLAYER_JUMP_STRENGTH, PLAYER_JUMP_TIME,
+};
+
+// ==== CONSTANTS ====
+/// distance (in px) between the standing positions tried
+pub const REACH_STEP: f32 = 8.0;
+/// longest simulated move (in ticks)
+pub const REACH_MAX_TICKS: u32 = 600;
+/// standing positions explored at most, per level
+pub const REACH_MAX_STATES: usize = 20000;
+/// ticks spent walking a single step, the player is blocked if it doesn't get further
+const WALK_TICKS: u32 = 60;
+/// ticks a running jump may take to reach an edge, further ones start from a closer position
+const RUN_TICKS: u32 = 15;
+/// ticks after a jump the held direction may change at, besides the top of the arc
+const TURN_TICKS: [u32; 3] = [6, 12, 18];
+
+// ==== LAYOUT ====
+
+/// What the player can touch or stand on in a level
+struct Layout {
+    /// centers and sizes of obstacles which are always present
+    obstacles: Vec<(Vec2, Vec2)>,
+    coins: Vec<Vec2>,
+    doors: Vec<(Vec2, Vec2)>,
+    spawn: Option<Vec2>,
+    bounds: Rect,
+}
+
+impl Layout {
+    /// same bounds as the loader, obstacles switched by signals are left out since they may be opened
+    fn new(objects: &[LevelObject]) -> Layout {
+        let mut layout = Layout {
+            obstacles: Vec::new(),
+            coins: Vec::new(),
+            doors: Vec::new(),
+            spawn: None,
+            bounds: Rect::new(0.0, 0.0, PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT),
+        };
+        let mut declared = None;
+        for object in objects {
+            layout.add(object, false, &mut declared);
+        }
+        layout.bounds = declared.unwrap_or(layout.bounds);
+        layout
+    }
+
+    fn add(&mut self, object: &LevelObject, triggered: bool, declared: &mut Option<Rect>) {
+        match object {
+            LevelObject::Obstacle((pos, size)) | LevelObject::Spring(pos, size, _, _) => {
+                self.bounds = self.bounds.union(Rect::from_center_size(*pos, *size));
+                if !triggered {
+                    self.obstacles.push((*pos, *size));
+                }
+            }
+            LevelObject::Coin(pos) => self.coins.push(*pos),
+            LevelObject::Door(pos, size) => self.doors.push((*pos, *size)),
+            LevelObject::PlayerPos(pos) => self.spawn = Some(*pos),
+            LevelObject::Triggered(_, _, inner) => self.add(inner, true, declared),
+            LevelObject::Bounds(rect) => *declared = Some(*rect),
+            LevelObject::Switch(..) | LevelObject::Ability(..) => {}
+        }
+    }
+}
+
+// ==== SIMULATION ====
+
+/// Player without abilities, moved by the same physics as update_player_physics
+#[derive(Clone, Copy)]
+struct Body {
+    position: Vec3,
+    speed: Vec3,
+    gravity: u32,
+    sides: [bool; 4],
+    jump_lock: bool,
+    /// ticks since the player last touched the ground
+    since_ground: u32,
+}
+
+impl Body {
+    fn standing(position: Vec2) -> Body {
+        Body {
+            position: position.extend(1.0),
+            speed: Vec3::ZERO,
+            gravity: 0,
+            sides: [true, false, false, false],
+            jump_lock: false,
+            since_ground: 0,
+        }
+    }
+
+    /// one tick of input, physics and collisions
+    fn step(&mut self, direction: f32, jump: bool, obstacles: &[(Vec2, Vec2)]) {
+        self.since_ground = if self.sides[0] {0} else {self.since_ground.saturating_add(1)};
+        let mut acceleration = Vec2::new(direction * PLAYER_ACCELERATION, 0.0);
+        if jump && self.since_ground <= coyote_ticks() && !self.jump_lock {
+            acceleration.y += PLAYER_JUMP_STRENGTH;
+            self.jump_lock = true;
+            self.gravity = PLAYER_JUMP_TIME;
+        }
+        apply_player_physics(&mut self.speed, &mut self.gravity, acceleration.extend(0.0), self.sides, false, false);
+        self.position += self.speed;
+        self.sides = [false; 4];
+        let size = Vec2::splat(PLAYER_SIZE);
+        for (pos, obstacle_size) in obstacles {
+            if collide(self.position.xy(), size, *pos, *obstacle_size) {
+                let side = push_out_of_obstacle(&mut self.position, &mut self.speed, size, *pos, *obstacle_size);
+                match side {
+                    0 => self.jump_lock = false,
+                    1 => self.gravity = 0,
+                    _ => {}
+                }
+                self.sides[side] = true;
+            }
+        }
+    }
+}
+
+/// ticks the player can still jump after leaving the ground, with the default coyote time
+fn coyote_ticks() -> u32 {
+    ms_to_ticks(PLAYER_COYOTE_TIME, 1.0 / 60.0)
+}
+
+/// Ways of moving from a standing position
+#[derive(Clone, Copy)]
+enum Move {
+    /// hold a direction for a step, walking off edges
+    Walk(f32),
+    /// jump holding one direction and another one after some ticks, or after the top of the arc
+    Jump(f32, f32, Option<u32>),
+    /// run off the edge in a direction and jump right after leaving it, then like Jump
+    RunJump(f32, f32, Option<u32>),
+    /// drop from the spawn point
+    Fall,
+}
+
+/// coins and doors touched by the player
+struct Touched {
+    coins: Vec<bool>,
+    doors: Vec<bool>,
+}
+
+/// Where the player stands after the move, none if it fell out of the level or didn't land in time
+fn simulate(layout: &Layout, start: Body, movement: Move, touched: &mut Touched) -> Option<Vec2> {
+    let mut body = start;
+    let mut falling = false;
+    // ticks since the jump
+    let mut jumped: Option<u32> = None;
+    let size = Vec2::splat(PLAYER_SIZE);
+    for tick in 0..REACH_MAX_TICKS {
+        let turned = |turn: Option<u32>| match (turn, jumped) {
+            (Some(turn), Some(air)) => air >= turn,
+            _ => falling,
+        };
+        let (direction, jump) = match movement {
+            Move::Walk(direction) => (direction, false),
+            Move::Jump(up, down, turn) => (if turned(turn) {down} else {up}, tick == 0),
+            Move::RunJump(_, _, _) if jumped.is_none() && tick >= RUN_TICKS => return None,
+            Move::RunJump(up, _, _) if jumped.is_none() => (up, !body.sides[0]),
+            Move::RunJump(up, down, turn) => (if turned(turn) {down} else {up}, false),
+            Move::Fall => (0.0, false),
+        };
+        body.step(direction, jump, &layout.obstacles);
+        jumped = jumped.map(|air| air + 1).or(body.jump_lock.then_some(0));
+        falling = falling || (jumped.is_some() && body.speed.y <= 0.0);
+        for (coin, reached) in layout.coins.iter().zip(touched.coins.iter_mut()) {
+            *reached |= collide(body.position.xy(), size, *coin, Vec2::splat(COIN_SIZE * 2.0));
+        }
+        for ((door, door_size), reached) in layout.doors.iter().zip(touched.doors.iter_mut()) {
+            *reached |= collide(body.position.xy(), size, *door, *door_size);
+        }
+        if body.position.y <= layout.bounds.min.y - PLAYER_DEATH_DEPTH {
+            return None;
+        }
+        let on_ground = body.sides[0] && tick > 0;
+        let landed = match movement {
+            Move::Walk(_) => on_ground && ((body.position.x - start.position.x).abs() >= REACH_STEP || tick >= WALK_TICKS),
+            // runs along the ground until the edge
+            Move::RunJump(..) => on_ground && jumped.is_some(),
+            _ => on_ground,
+        };
+        if landed {
+            return Some(body.position.xy());
+        }
+    }
+    None
+}
+
+/// Height and length (in px) of a full jump holding a direction, on flat ground
+pub fn jump_envelope() -> (f32, f32) {
+    let floor = vec![(Vec2::ZERO, Vec2::new(1.0e6, 2.0))];
+    let start = Vec2::new(0.0, 1.0 + PLAYER_SIZE / 2.0);
+    let mut body = Body::standing(start);
+    let mut height: f32 = 0.0;
+    for tick in 0..REACH_MAX_TICKS {
+        body.step(1.0, tick == 0, &floor);
+        height = height.max(body.position.y - start.y);
+        if tick > 0 && body.sides[0] {break;}
+    }
+    (height, body.position.x - start.x)
+}
+
+// ==== ANALYSIS ====
+
+/// Which coins and doors of a level the player can get to from the spawn point
+pub struct Reachability {
+    /// positions (in game coords) of the coins and whether they can be reached
+    pub coins: Vec<(Vec2, bool)>,
+    pub doors: Vec<(Vec2, bool)>,
+    /// coins needed to open the doors, Score.needed of the level
+    pub needed: usize,
+    /// standing positions found
+    pub states: usize,
+}
+
+impl Reachability {
+    pub fn reachable_coins(&self) -> usize {
+        self.coins.iter().filter(|c| c.1).count()
+    }
+
+    /// enough coins can be collected and a door can be entered
+    pub fn completable(&self) -> bool {
+        self.reachable_coins() >= self.needed && self.doors.iter().any(|d| d.1)
+    }
+}
+
+/// Explore every standing position the player gets to by walking and jumping from the spawn point.<br>
+/// Abilities, wall jumps and springs aren't used, and switched obstacles count as open,
+/// so a level flagged here may still be completable with them.
+pub fn analyze_level(objects: &[LevelObject]) -> Reachability {
+    let layout = Layout::new(objects);
+    let mut touched = Touched {
+        coins: vec![false; layout.coins.len()],
+        doors: vec![false; layout.doors.len()],
+    };
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    if let Some(spawn) = layout.spawn {
+        let mut body = Body::standing(spawn);
+        body.sides = [false; 4];
+        if let Some(landing) = simulate(&layout, body, Move::Fall, &mut touched) {
+            queue.push_back(landing);
+        }
+    }
+    // every pair of directions, turning at each time when they differ
+    let mut moves = vec![Move::Walk(-1.0), Move::Walk(1.0)];
+    for up in [-1.0, 0.0, 1.0] {
+        for down in [-1.0, 0.0, 1.0] {
+            let turns = if up == down {vec![None]} else {TURN_TICKS.iter().map(|t| Some(*t)).chain([None]).collect()};
+            for turn in turns {
+                moves.push(Move::Jump(up, down, turn));
+                if up != 0.0 {
+                    moves.push(Move::RunJump(up, down, turn));
+                }
+            }
+        }
+    }
+    while let Some(position) = queue.pop_front() {
+        let key = ((position.x / REACH_STEP).round() as i32, position.y.round() as i32);
+        if !visited.insert(key) || visited.len() > REACH_MAX_STATES {continue;}
+        for movement in moves.iter() {
+            if let Some(landing) = simulate(&layout, Body::standing(position), *movement, &mut touched) {
+                queue.push_back(landing);
+            }
+        }
+    }
+    Reachability {
+        coins: layout.coins.iter().copied().zip(touched.coins).collect(),
+        doors: layout.doors.iter().map(|d| d.0).zip(touched.doors).collect(),
+        needed: layout.coins.len(),
+        states: visited.len(),
+    }
+}
+
+/// Analyze every level of every run in a directory, returns the number of levels which can't be completed
+pub fn analyze_levels(path: &str) -> usize {
+    let paths = match level_paths(path) {
+        Ok(paths) => paths,
+        Err(e) => {
+            println!("[REACH] {}", e);
+            return 1;
+        }
+    };
+    let (height, length) = jump_envelope();
+    println!("[REACH] Jumps reach {:.0}px high and {:.0}px far", height, length);
+    let mut flagged = 0;
+    for level_path in paths {
+        let Some((objects, _)) = read_level_objects(&level_path) else {
+            println!("[REACH] {}: Couldn't read the level", level_path);
+            flagged += 1;
+            continue;
+        };
+        let reach = analyze_level(&objects);
+        let doors = reach.doors.iter().filter(|d| d.1).count();
+        println!(
+            "[REACH] {}: {}/{} coins and {}/{} doors reachable ({} positions)",
+            level_path, reach.reachable_coins(), reach.coins.len(), doors, reach.doors.len(), reach.states
+        );
+        for (coin, _) in reach.coins.iter().filter(|c| !c.1) {
+            println!("[REACH] {}: The coin at {} can't be reached", level_path, format_pos(legacy_pos(*coin)));
+        }
+        for (door, _) in reach.doors.iter().filter(|d| !d.1) {
+            println!("[REACH] {}: The door at {} can't be reached", level_path, format_pos(legacy_pos(*door)));
+        }
+        if !reach.completable() {
+            println!("[REACH] {}: Can't be completed, {} coins are needed", level_path, reach.needed);
+            flagged += 1;
+        }
+    }
+    println!("[REACH] {} levels can't be completed", flagged);
+    flagged
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::legacy_loading::test_level;
+
+    use super::*;
+
+    /// keep the player from falling off either end of the floor
+    const WALLS: [&str; 2] = ["BARRIER -10 0 10 480", "BARRIER 640 0 10 480"];
+
+    #[test]
+    fn door_across_the_floor_is_reachable() {
+        let reach = analyze_level(&test_level(&[WALLS[0], WALLS[1], "COIN 300 430", "DOOR 600 380 40 80"]));
+        assert_eq!(reach.coins.iter().map(|c| c.1).collect::<Vec<_>>(), vec![true]);
+        assert_eq!(reach.doors.iter().map(|d| d.1).collect::<Vec<_>>(), vec![true]);
+        assert!(reach.completable());
+    }
+
+    #[test]
+    fn coin_above_jumps_and_door_behind_wall_are_flagged() {
+        // the door is behind a wall the player can't jump over
+        let reach = analyze_level(&test_level(&[WALLS[0], WALLS[1], "COIN 300 100", "COIN 300 430", "DOOR 600 380 40 80", "BARRIER 450 0 10 460"]));
+        assert_eq!(reach.coins.iter().map(|c| c.1).collect::<Vec<_>>(), vec![false, true]);
+        assert_eq!(reach.doors.iter().map(|d| d.1).collect::<Vec<_>>(), vec![false]);
+        assert_eq!(reach.reachable_coins(), 1);
+        assert!(!reach.completable());
+    }
+}
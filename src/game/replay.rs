@@ -0,0 +1,139 @@
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+
+use crate::{data::Leaderboard, AppState, SimulationState};
+
+use super::{handle_player_input, LoadRunEvent, PlayerAction, PlayerInput};
+
+pub const PATH_REPLAYS: &str = "./config/replays/";
+
+/// File name for a new replay of the run at the given path, not taken by any replay yet
+pub fn new_replay_file(run: &str) -> String {
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis());
+    let base = format!("{}_{}", Leaderboard::run_key(run), stamp);
+    (0..)
+        .map(|n| if n == 0 {format!("{}.replay", base)} else {format!("{}_{}.replay", base, n)})
+        .find(|name| !Path::new(&format!("{}{}", PATH_REPLAYS, name)).exists())
+        .unwrap_or(base)
+}
+
+// ==== PLUGIN ====
+
+/// Records the player's input on every tick of the run being played
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //.
+        .init_resource::<Replay>()
+        .add_systems(FixedUpdate, reset_replay
+            .before(record_player_input)
+            .run_if(in_state(AppState::Game)))
+        // reads the same events as handle_player_input, on the same tick
+        .add_systems(FixedUpdate, record_player_input
+            .before(handle_player_input)
+            .run_if(in_state(AppState::Game))
+            .run_if(in_state(SimulationState::Running)))
+        //.
+        ;
+    }
+}
+
+// ==== RESOURCE ====
+
+/// Input of a run, the resource holds the one being recorded
+#[derive(Resource, Clone, PartialEq, Debug, Default)]
+pub struct Replay {
+    /// path of the run
+    pub run: String,
+    /// number of ticks recorded
+    pub length: u32,
+    /// actions in the order they were performed, with their tick
+    pub inputs: Vec<(u32, PlayerAction)>,
+}
+
+impl Replay {
+    /// read a replay, one input per line after the header:
+    /// ```text
+    /// RUN <path>
+    /// LENGTH <ticks>
+    /// <tick> <ACTION>
+    /// ```
+    pub fn parse(buff: &str) -> Result<Replay, String> {
+        let mut replay = Replay::default();
+        for (n, line) in buff.lines().enumerate() {
+            let (keyword, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            match keyword {
+                "" => {}
+                "RUN" => replay.run = rest.to_string(),
+                "LENGTH" => replay.length = rest.parse().map_err(|_| format!("Invalid length at line number {}", n))?,
+                tick => match (tick.parse(), PlayerAction::from_name(rest)) {
+                    (Ok(tick), Some(action)) => replay.inputs.push((tick, action)),
+                    _ => return Err(format!("Invalid input at line number {}", n)),
+                },
+            }
+        }
+        Ok(replay)
+    }
+
+    pub fn load(path: &str) -> Option<Replay> {
+        let mut buff = String::new();
+        if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut buff)) {
+            println!("[REPLAY] Couldn't read {} due {}", path, e);
+            return None;
+        }
+        Replay::parse(&buff).map_err(|e| println!("[REPLAY] Error in {}: {}", path, e)).ok()
+    }
+
+    /// the replay without its RUN line
+    pub fn inputs_text(&self) -> String {
+        let mut buff = format!("LENGTH {}\n", self.length);
+        for (tick, action) in self.inputs.iter() {
+            buff += &format!("{} {}\n", tick, action.name());
+        }
+        buff
+    }
+
+    pub fn save(&self, path: &str) {
+        let buff = format!("RUN {}\n{}", self.run, self.inputs_text());
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        match File::create(path).and_then(|mut f| f.write_all(buff.as_bytes())) {
+            Ok(_) => println!("[REPLAY] Saved replay to {}", path),
+            Err(e) => println!("[REPLAY] Couldn't save replay to {} due {}", path, e),
+        }
+    }
+}
+
+// ==== SYSTEMS ====
+
+pub fn reset_replay(
+    mut event_reader: EventReader<LoadRunEvent>,
+    mut replay: ResMut<Replay>,
+) {
+    if let Some(event) = event_reader.read().last() {
+        *replay = Replay {
+            run: event.path.clone(),
+            ..default()
+        };
+    }
+}
+
+pub fn record_player_input(
+    mut event_reader: EventReader<PlayerInput>,
+    mut replay: ResMut<Replay>,
+) {
+    let tick = replay.length;
+    for input in event_reader.read() {
+        replay.inputs.push((tick, input.0));
+    }
+    replay.length += 1;
+}
@@ -0,0 +1,318 @@
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    time::Duration,
+};
+
+use bevy::{input::InputPlugin, prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    audio::PlaySoundEvent,
+    data::{InputBindings, Leaderboard, LeaderboardEntry, SaveData, SelectedRun},
+    menu::SpawnScoreMenuEvent,
+    AppState, HudState, SimulationState,
+};
+
+use super::{
+    handle_player_input, new_replay_file, GameObjectsPlugin, ImpactEvent, LevelCompleteEvent, LoaderPlugin, PlayerInput,
+    PlayerPlugin, ProgressPlugin, Replay, RunProgress, PATH_REPLAYS,
+};
+
+// ==== CONSTANTS ====
+/// frames simulated past the end of a replay before giving up on it
+pub const SIMULATION_MARGIN: u32 = 120;
+/// largest difference (in seconds) between a claimed and a simulated time
+pub const VERIFY_TOLERANCE: f32 = 0.01;
+
+// ==== SIMULATION ====
+
+/// Finish of a re-simulated run
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct SimulatedRun {
+    pub time: f32,
+    pub coins: usize,
+    pub deaths: usize,
+}
+
+/// Replay fed to the player instead of the keyboard, one tick at a time
+#[derive(Resource)]
+struct Playback {
+    replay: Replay,
+    tick: u32,
+    next: usize,
+}
+
+fn play_replay_input(mut playback: ResMut<Playback>, mut event_writer: EventWriter<PlayerInput>) {
+    let tick = playback.tick;
+    while let Some(&(input_tick, action)) = playback.replay.inputs.get(playback.next) {
+        if input_tick > tick {break;}
+        if input_tick == tick {
+            event_writer.send(PlayerInput(action));
+        }
+        playback.next += 1;
+    }
+    playback.tick += 1;
+}
+
+fn finish_simulation(
+    mut commands: Commands,
+    mut event_reader: EventReader<LevelCompleteEvent>,
+    progress: Res<RunProgress>,
+) {
+    for event in event_reader.read().filter(|e| e.last) {
+        commands.insert_resource(SimulatedRun {
+            time: event.run_time,
+            coins: event.run_coins,
+            deaths: progress.deaths,
+        });
+    }
+}
+
+/// Play a replay through the game's own systems, without a window and one tick per frame.<br>
+/// Returns how the run ended, none if its last level wasn't finished.
+pub fn simulate_replay(replay: &Replay) -> Option<SimulatedRun> {
+    let tick = Duration::from_secs_f64(1.0 / 60.0);
+    let mut app = App::new();
+    app
+        //.
+        .add_plugins((MinimalPlugins, InputPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(tick))
+        .insert_resource(Time::<Fixed>::from_duration(tick))
+        .insert_state(AppState::Game)
+        .init_state::<SimulationState>()
+        .init_state::<HudState>()
+        // resources and events of the plugins left out
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<ColorMaterial>>()
+        .init_resource::<InputBindings>()
+        .init_resource::<SaveData>()
+        .add_event::<PlaySoundEvent>()
+        .add_event::<SpawnScoreMenuEvent>()
+        .add_event::<ImpactEvent>()
+        .insert_resource(SelectedRun(replay.run.clone()))
+        .insert_resource(Playback { replay: replay.clone(), tick: 0, next: 0 })
+        .add_plugins((PlayerPlugin, GameObjectsPlugin, LoaderPlugin, ProgressPlugin))
+        .add_systems(FixedUpdate, play_replay_input
+            .before(handle_player_input)
+            .run_if(in_state(SimulationState::Running)))
+        .add_systems(Update, finish_simulation)
+        //.
+        ;
+    for _ in 0..replay.length + SIMULATION_MARGIN {
+        app.update();
+        if let Some(run) = app.world.get_resource::<SimulatedRun>() {
+            return Some(*run);
+        }
+    }
+    None
+}
+
+/// whether the replay finishes the run with the entry's results
+pub fn verify_entry(entry: &LeaderboardEntry, replay: &Replay) -> Result<(), String> {
+    match simulate_replay(replay) {
+        None => Err("the replay doesn't finish the run".to_string()),
+        Some(run) if (run.time - entry.time).abs() > VERIFY_TOLERANCE => {
+            Err(format!("the replay finishes in {}s, not {}s", run.time, entry.time))
+        }
+        Some(run) if run.coins != entry.coins || run.deaths != entry.deaths => Err(format!(
+            "the replay ends with {} coins and {} deaths, not {} and {}",
+            run.coins, run.deaths, entry.coins, entry.deaths
+        )),
+        Some(_) => Ok(()),
+    }
+}
+
+// ==== EXPORT & IMPORT ====
+
+/// Write a run's leaderboard with every replay into one file:
+/// ```text
+/// RUN <path>
+/// ENTRY <time> <coins> <deaths> <name>
+/// LENGTH <ticks>
+/// <tick> <ACTION>
+/// ```
+/// with an ENTRY line and its replay for every entry.
+pub fn export_leaderboard(run: &str, path: &str) -> Result<usize, String> {
+    let leaderboard = Leaderboard::load(run);
+    let mut buff = format!("RUN {}\n", run);
+    let mut exported = 0;
+    for entry in leaderboard.entries.iter() {
+        let Some(replay) = Replay::load(&format!("{}{}", PATH_REPLAYS, entry.replay)) else {
+            println!("[EXPORT] Skipping {}, its replay is missing", entry.name);
+            continue;
+        };
+        buff += &format!("ENTRY {} {} {} {}\n", entry.time, entry.coins, entry.deaths, entry.name);
+        buff += &replay.inputs_text();
+        exported += 1;
+    }
+    File::create(path)
+        .and_then(|mut f| f.write_all(buff.as_bytes()))
+        .map_err(|e| format!("Couldn't write {} due {}", path, e))?;
+    Ok(exported)
+}
+
+/// Add the entries of an exported leaderboard whose replays check out, returns how many were added
+pub fn import_leaderboard(path: &str) -> Result<usize, String> {
+    let mut buff = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut buff))
+        .map_err(|e| format!("Couldn't read {} due {}", path, e))?;
+    let mut lines = buff.lines();
+    let run = lines.next()
+        .and_then(|l| l.strip_prefix("RUN "))
+        .ok_or(format!("{} doesn't start with a RUN line", path))?
+        .to_string();
+    // every entry is its ENTRY line followed by its replay
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for line in lines {
+        match (line.strip_prefix("ENTRY "), entries.last_mut()) {
+            (Some(header), _) => entries.push((header.to_string(), String::new())),
+            (None, Some((_, replay))) => *replay += &format!("{}\n", line),
+            (None, None) => return Err(format!("{} has a replay without an entry", path)),
+        }
+    }
+    let mut leaderboard = Leaderboard::load(&run);
+    let mut imported = 0;
+    for (header, text) in entries.iter() {
+        let segs: Vec<&str> = header.splitn(4, ' ').collect();
+        let entry = match segs[..] {
+            [time, coins, deaths, name] => match (time.parse(), coins.parse(), deaths.parse()) {
+                (Ok(time), Ok(coins), Ok(deaths)) => LeaderboardEntry {
+                    name: name.to_string(),
+                    time,
+                    coins,
+                    deaths,
+                    replay: new_replay_file(&run),
+                },
+                _ => {
+                    println!("[IMPORT] Skipping an invalid entry '{}'", header);
+                    continue;
+                }
+            },
+            _ => {
+                println!("[IMPORT] Skipping an invalid entry '{}'", header);
+                continue;
+            }
+        };
+        if leaderboard.entries.iter().any(|e| e.name == entry.name && e.time == entry.time) {
+            println!("[IMPORT] {} {}s is already on the leaderboard", entry.name, entry.time);
+            continue;
+        }
+        let replay = match Replay::parse(text) {
+            Ok(replay) => Replay { run: run.clone(), ..replay },
+            Err(e) => {
+                println!("[IMPORT] Rejected {} {}s: {}", entry.name, entry.time, e);
+                continue;
+            }
+        };
+        if let Err(e) = verify_entry(&entry, &replay) {
+            println!("[IMPORT] Rejected {} {}s: {}", entry.name, entry.time, e);
+            continue;
+        }
+        println!("[IMPORT] Verified {} {}s", entry.name, entry.time);
+        let replay_file = entry.replay.clone();
+        let dropped = leaderboard.insert(entry);
+        for entry in dropped.iter().filter(|e| e.replay != replay_file) {
+            let _ = fs::remove_file(format!("{}{}", PATH_REPLAYS, entry.replay));
+        }
+        if !dropped.iter().any(|e| e.replay == replay_file) {
+            replay.save(&format!("{}{}", PATH_REPLAYS, replay_file));
+            imported += 1;
+        }
+    }
+    if imported > 0 {
+        leaderboard.save(&run);
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::game::PlayerAction::{self, MoveLeft, MoveRight};
+
+    use super::*;
+
+    /// Inputs finishing the first level of og4 with every coin: ticks of the segment,
+    /// direction held, whether jump is pressed at its start and released at its end
+    const OG4_ROUTE: &[(u32, Option<PlayerAction>, bool, bool)] = &[
+        (75, Some(MoveRight), false, false),
+        // onto the low platform, through the coin under the left one
+        (30, Some(MoveRight), true, true),
+        (10, None, false, false),
+        (30, Some(MoveLeft), true, true),
+        (20, None, false, false),
+        // onto the long platform and its coin
+        (8, None, true, false),
+        (30, Some(MoveRight), false, true),
+        (10, None, false, false),
+        // off its edge through the coin in the air
+        (92, Some(MoveRight), false, false),
+        (60, Some(MoveRight), true, true),
+        // the coin on the floor, then the one over the door
+        (300, Some(MoveRight), false, false),
+        (40, None, true, true),
+    ];
+
+    /// One level run holding the first level of og4, so finishing it finishes the run
+    fn og4_run(name: &str) -> String {
+        let run = env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        fs::create_dir_all(&run).unwrap();
+        fs::copy("assets/levels/og4/0", run.join("0")).unwrap();
+        fs::copy("assets/levels/og4/info", run.join("info")).unwrap();
+        run.to_string_lossy().to_string()
+    }
+
+    fn og4_replay(run: String) -> Replay {
+        let mut replay = Replay { run, ..default() };
+        for &(ticks, direction, jump_start, jump_end) in OG4_ROUTE {
+            let tick = replay.length;
+            if jump_start {
+                replay.inputs.push((tick, PlayerAction::JumpStart));
+            }
+            if let Some(direction) = direction {
+                replay.inputs.extend((tick..tick + ticks).map(|t| (t, direction)));
+            }
+            if jump_end {
+                replay.inputs.push((tick + ticks, PlayerAction::JumpEnd));
+            }
+            replay.length += ticks;
+        }
+        replay
+    }
+
+    #[test]
+    fn resimulated_replay_matches() {
+        let run = og4_run("resimulated_replay_matches");
+        let replay = og4_replay(run.clone());
+        let finish = simulate_replay(&replay).expect("the replay doesn't finish the level");
+        assert_eq!(finish.coins, 5);
+        assert_eq!(finish.deaths, 0);
+        // same result again, and after a round trip through the replay file
+        assert_eq!(simulate_replay(&replay), Some(finish));
+        let parsed = Replay { run: run.clone(), ..Replay::parse(&replay.inputs_text()).unwrap() };
+        assert_eq!(simulate_replay(&parsed), Some(finish));
+        let _ = fs::remove_dir_all(run);
+    }
+
+    #[test]
+    fn tampered_time_is_rejected() {
+        let run = og4_run("tampered_time_is_rejected");
+        let replay = og4_replay(run.clone());
+        let finish = simulate_replay(&replay).expect("the replay doesn't finish the level");
+        let entry = LeaderboardEntry {
+            name: "player".to_string(),
+            time: finish.time,
+            coins: finish.coins,
+            deaths: finish.deaths,
+            replay: String::new(),
+        };
+        assert_eq!(verify_entry(&entry, &replay), Ok(()));
+        let faster = LeaderboardEntry { time: finish.time - 1.0, ..entry.clone() };
+        assert!(verify_entry(&faster, &replay).is_err());
+        let richer = LeaderboardEntry { coins: finish.coins + 1, ..entry };
+        assert!(verify_entry(&richer, &replay).is_err());
+        let _ = fs::remove_dir_all(run);
+    }
+}
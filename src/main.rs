@@ -1,20 +1,59 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
-use bevy::{ prelude::*, window::{PrimaryWindow, WindowResolution}};
+use bevy::{ prelude::*, window::WindowResolution};
+use audio::SoundPlugin;
+use camera::CameraPlugin;
+use editor::EditorPlugin;
+use data::{BindingsPlugin, SavePlugin, Settings, SettingsPlugin, PATH_SETTINGS};
 use game::GamePlugin;
 use menu::MenuPLugin;
 
+mod audio;
+mod camera;
 mod data;
+mod editor;
 mod game;
 mod menu;
 
+/// default window size relative to the original 640x480
 pub const WINDOW_SCALE: f32 = 2.0;
 
 fn main() {
+    // commands run without opening the game
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => {}
+        ["export", run, path] => {
+            match game::export_leaderboard(run, path) {
+                Ok(n) => println!("[EXPORT] Exported {} entries to {}", n, path),
+                Err(e) => println!("[EXPORT] {}", e),
+            }
+            return;
+        }
+        ["import", path] => {
+            match game::import_leaderboard(path) {
+                Ok(n) => println!("[IMPORT] Imported {} entries from {}", n, path),
+                Err(e) => println!("[IMPORT] {}", e),
+            }
+            return;
+        }
+        ["lint"] => std::process::exit((data::lint_levels(data::PATH_LEVELS) > 0) as i32),
+        ["lint", path] => std::process::exit((data::lint_levels(path) > 0) as i32),
+        ["reach"] => std::process::exit((game::analyze_levels(data::PATH_LEVELS) > 0) as i32),
+        ["reach", path] => std::process::exit((game::analyze_levels(path) > 0) as i32),
+        _ => {
+            println!("Usage: game [export <run path> <file> | import <file> | lint [levels directory] | reach [levels directory]]");
+            return;
+        }
+    }
+    let settings = Settings::load(PATH_SETTINGS);
+    let window_size = settings.window_size();
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Moving Square [Bevy] beta".to_string(),
-                resolution: WindowResolution::new(WINDOW_SCALE*640.0, WINDOW_SCALE*480.0),
+                resolution: WindowResolution::new(window_size.x, window_size.y),
+                mode: settings.window_mode(),
+                present_mode: settings.present_mode(),
                 ..Default::default()
             }),
             ..Default::default()
@@ -23,30 +62,23 @@ fn main() {
         .init_state::<AppState>()
         .init_state::<SimulationState>()
         .init_state::<HudState>()
+        .insert_resource(settings)
         // FixedUpdate Time
         .insert_resource(Time::<Fixed>::from_seconds(1.0 / 60.0))
-        .add_systems(Startup, spawn_camera)
         // Plugins
-        .add_plugins((GamePlugin,MenuPLugin))
+        .add_plugins((CameraPlugin,SoundPlugin,BindingsPlugin,SettingsPlugin,SavePlugin,GamePlugin,MenuPLugin,EditorPlugin))
         .run();
 }
 
-pub fn spawn_camera(mut commands: Commands, window_query: Query<&Window, With<PrimaryWindow>>) {
-    let window = window_query.get_single().unwrap();
-
-    commands.spawn(Camera2dBundle {
-        transform: Transform::from_xyz(window.width() / 2.0, window.height() / 2.0, 0.0),
-        ..default()
-    });
-    println!("[INFO] Window size {}x{} , spawning camera in center",window.width(),window.height());
-}
-
 #[derive(Default, States, Debug, Hash, PartialEq, Eq, Clone)]
 pub enum AppState {
     #[default]
     MainMenu,
     Levels,
+    Controls,
+    Settings,
     Game,
+    Editor,
 }
 
 #[derive(Default, States, Debug, Hash, PartialEq, Eq, Clone)]
@@ -62,4 +94,5 @@ pub enum HudState {
     None,
     Pause,
     Score,
+    Settings,
 }
\ No newline at end of file
@@ -0,0 +1,190 @@
+use std::{fs::File, io::Read};
+
+use bevy::prelude::*;
+
+use super::{
+    interactions::{MenuAction, MenuButton},
+    styles::*,
+};
+
+pub const PATH_MENUS: &str = "./assets/menus/";
+
+// ==== COMPONENTS ====
+
+/// Root node of every menu spawned by MenuBuilder, despawned by its name
+#[derive(Component)]
+pub struct MenuRoot(pub String);
+
+// ==== BUILDER ====
+
+pub enum MenuItem {
+    Title(String),
+    Text(String),
+    Button(String, MenuAction),
+    /// button using WIDE_BUTTON_STYLE, for longer labels
+    WideButton(String, MenuAction),
+}
+
+/// Description of a menu: its layout and a column of items, spawned with `spawn`
+pub struct MenuBuilder {
+    pub name: String,
+    pub style: Style,
+    pub items: Vec<MenuItem>,
+}
+
+impl MenuBuilder {
+    pub fn new(name: &str, style: Style) -> MenuBuilder {
+        MenuBuilder {
+            name: name.to_string(),
+            style,
+            items: Vec::new(),
+        }
+    }
+
+    pub fn title(mut self, text: impl Into<String>) -> Self {
+        self.items.push(MenuItem::Title(text.into()));
+        self
+    }
+
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.items.push(MenuItem::Text(text.into()));
+        self
+    }
+
+    pub fn button(mut self, label: impl Into<String>, action: MenuAction) -> Self {
+        self.items.push(MenuItem::Button(label.into(), action));
+        self
+    }
+
+    pub fn wide_button(mut self, label: impl Into<String>, action: MenuAction) -> Self {
+        self.items.push(MenuItem::WideButton(label.into(), action));
+        self
+    }
+
+    pub fn spawn(self, commands: &mut Commands, asset_server: &Res<AssetServer>) -> Entity {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: self.style,
+                    ..default()
+                },
+                MenuRoot(self.name),
+            ))
+            .with_children(|parent| {
+                for item in self.items {
+                    match item {
+                        MenuItem::Title(text) => spawn_text(parent, text, get_title_text_style(asset_server)),
+                        MenuItem::Text(text) => spawn_text(parent, text, get_normal_text_style(asset_server)),
+                        MenuItem::Button(label, action) => {
+                            spawn_button(parent, label, action, NORMAL_BUTTON_STYLE, asset_server)
+                        }
+                        MenuItem::WideButton(label, action) => {
+                            spawn_button(parent, label, action, WIDE_BUTTON_STYLE, asset_server)
+                        }
+                    }
+                }
+            })
+            .id()
+    }
+
+    /// Read a menu from a file, one item per line:
+    /// ```text
+    /// STYLE MAIN|PAUSE|SCORE|SETTINGS
+    /// TITLE <text>
+    /// TEXT <text>
+    /// BUTTON <ACTION> [argument] "<label>"
+    /// WIDE_BUTTON <ACTION> [argument] "<label>"
+    /// ```
+    pub fn load(name: &str) -> MenuBuilder {
+        let path = format!("{}{}", PATH_MENUS, name);
+        let mut menu = MenuBuilder::new(name, MAIN_MENU_STYLE);
+        let mut buff = String::new();
+        if let Err(e) = File::open(&path).and_then(|mut f| f.read_to_string(&mut buff)) {
+            println!("[MENU] Couldn't read menu at {} due {}", path, e);
+            // leave the player a way out
+            return menu.button("Exit", MenuAction::Exit);
+        }
+        for (n, line) in buff.lines().enumerate() {
+            let (keyword, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            match keyword {
+                "" => {}
+                "STYLE" => match rest {
+                    "MAIN" => menu.style = MAIN_MENU_STYLE,
+                    "PAUSE" => menu.style = PAUSE_MENU_STYLE,
+                    "SCORE" => menu.style = SCORE_MENU_STYLE,
+                    "SETTINGS" => menu.style = SETTINGS_MENU_STYLE,
+                    _ => println!("[MENU] Error at line number {} of {}: Unknown style {}", n, path, rest),
+                },
+                "TITLE" => menu = menu.title(rest),
+                "TEXT" => menu = menu.text(rest),
+                "BUTTON" | "WIDE_BUTTON" => {
+                    // the label is quoted, so it may contain spaces
+                    let Some((action, label)) = rest.split_once('"') else {
+                        println!("[MENU] Error at line number {} of {}: Missing label", n, path);
+                        continue;
+                    };
+                    let label = label.trim_end_matches('"');
+                    match MenuAction::parse(&action.split_ascii_whitespace().collect::<Vec<&str>>()) {
+                        Some(action) if keyword == "BUTTON" => menu = menu.button(label, action),
+                        Some(action) => menu = menu.wide_button(label, action),
+                        None => println!("[MENU] Error at line number {} of {}: Unknown action {}", n, path, action),
+                    }
+                }
+                _ => println!("[MENU] Error at line number {} of {}: Unknown keyword {}", n, path, keyword),
+            }
+        }
+        menu
+    }
+}
+
+fn spawn_text(parent: &mut ChildBuilder, text: String, style: TextStyle) {
+    parent.spawn(TextBundle {
+        text: Text {
+            sections: vec![TextSection::new(text, style)],
+            justify: JustifyText::Center,
+            linebreak_behavior: bevy::text::BreakLineOn::NoWrap,
+        },
+        ..default()
+    });
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    label: String,
+    action: MenuAction,
+    style: Style,
+    asset_server: &Res<AssetServer>,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style,
+                background_color: NORMAL_BUTTON_COLOR.into(),
+                ..default()
+            },
+            MenuButton(action),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text {
+                    sections: vec![TextSection::new(label, get_normal_text_style(asset_server))],
+                    justify: JustifyText::Center,
+                    ..default()
+                },
+                ..default()
+            });
+        });
+}
+
+// ==== SYSTEMS ====
+
+/// System despawning the menus with the given name, e.g. `OnExit(..), despawn_menu("main")`
+pub fn despawn_menu(name: &'static str) -> impl Fn(Commands, Query<(Entity, &MenuRoot)>) {
+    move |mut commands: Commands, menu_query: Query<(Entity, &MenuRoot)>| {
+        for (entity, root) in menu_query.iter() {
+            if root.0 == name {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+
+use crate::{
+    data::{InputAction, InputBindings, PATH_CONTROLS},
+    AppState,
+};
+
+use super::{
+    focus::MenuFocus,
+    builder::{despawn_menu, MenuBuilder},
+    interactions::{MenuAction, MenuButton},
+    styles::*,
+};
+
+pub struct ControlsMenuPlugin;
+
+impl Plugin for ControlsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //
+        .init_resource::<RebindingAction>()
+        .add_systems(OnEnter(AppState::Controls), spawn_controls_menu)
+        .add_systems(Update, (
+            capture_rebind_key,
+            update_rebind_labels
+        ).chain().run_if(in_state(AppState::Controls)))
+        .add_systems(OnExit(AppState::Controls), (despawn_menu("controls"), stop_rebinding))
+        //
+        ;
+    }
+}
+
+/// Action waiting for a new key, if any
+#[derive(Resource, Default)]
+pub struct RebindingAction {
+    pub action: Option<InputAction>,
+    /// action which lost its key to the last rebound one, and the rebound one
+    pub conflict: Option<(InputAction, InputAction)>,
+}
+
+pub fn spawn_controls_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    bindings: Res<InputBindings>,
+) {
+    let _main_menu_entity = build_controls_menu(&bindings).spawn(&mut commands, &asset_server);
+}
+
+pub fn stop_rebinding(
+    mut rebinding: ResMut<RebindingAction>,
+    mut focus: ResMut<MenuFocus>,
+) {
+    *rebinding = RebindingAction::default();
+    focus.locked = false;
+}
+
+pub fn build_controls_menu(bindings: &InputBindings) -> MenuBuilder {
+    let mut menu = MenuBuilder::new("controls", SETTINGS_MENU_STYLE);
+    for action in InputAction::ALL {
+        menu = menu.wide_button(rebind_label(action, bindings, &RebindingAction::default()), MenuAction::Rebind(action));
+    }
+    menu.button("Reset", MenuAction::ResetControls)
+        .button("Back", MenuAction::Back)
+}
+
+fn rebind_label(action: InputAction, bindings: &InputBindings, rebinding: &RebindingAction) -> String {
+    match rebinding.conflict {
+        _ if rebinding.action == Some(action) => format!("{}: press a key", action.label()),
+        Some((lost, by)) if lost == action => {
+            format!("{}: {} (key moved to {})", action.label(), bindings.describe(action), by.label())
+        }
+        _ => format!("{}: {}", action.label(), bindings.describe(action)),
+    }
+}
+
+/// Bind the first key pressed while an action is waiting for one
+pub fn capture_rebind_key(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut bindings: ResMut<InputBindings>,
+    mut rebinding: ResMut<RebindingAction>,
+    mut focus: ResMut<MenuFocus>,
+) {
+    // navigation keys are rebound instead of moving the focus
+    focus.locked = rebinding.action.is_some();
+    // skip the key which started rebinding (Enter when using the keyboard)
+    if rebinding.is_changed() {return;}
+    if let Some(action) = rebinding.action {
+        if let Some(key) = keyboard_input.get_just_pressed().next() {
+            let conflict = bindings.rebind(action, *key);
+            if let Some(lost) = conflict {
+                println!("[BINDINGS] {:?} was bound to {}, swapped it with {}", key, lost.label(), action.label());
+            }
+            bindings.save(PATH_CONTROLS);
+            rebinding.action = None;
+            rebinding.conflict = conflict.map(|lost| (lost, action));
+            focus.locked = false;
+        }
+    }
+}
+
+pub fn update_rebind_labels(
+    button_query: Query<(&MenuButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+    bindings: Res<InputBindings>,
+    rebinding: Res<RebindingAction>,
+) {
+    if !bindings.is_changed() && !rebinding.is_changed() {return;}
+    for (button, children) in button_query.iter() {
+        let MenuAction::Rebind(action) = button.0 else {continue};
+        for &child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.sections[0].value = rebind_label(action, &bindings, &rebinding);
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use bevy::{prelude::*, ui::UiSystem};
+
+use crate::data::{InputAction, InputBindings, STICK_DEADZONE};
+
+use super::{
+    interactions::{MenuAction, MenuButton},
+    styles::{HOVERED_BUTTON_COLOR, NORMAL_BUTTON_COLOR},
+};
+
+// ==== PLUGIN ====
+
+/// Lets menus be navigated without a mouse.<br>
+/// The focused button is highlighted and confirming presses it, so the usual interaction systems handle it.
+pub struct FocusPlugin;
+
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //.
+        .init_resource::<MenuFocus>()
+        .add_event::<FocusInput>()
+        .add_systems(PreUpdate, (
+            (read_keyboard_focus_input,read_gamepad_focus_input),
+            move_menu_focus,
+            highlight_menu_focus
+        ).chain().after(UiSystem::Focus))
+        //.
+        ;
+    }
+}
+
+// ==== RESOURCES & EVENTS ====
+
+/// Button currently selected by keyboard or gamepad
+#[derive(Resource, Default)]
+pub struct MenuFocus {
+    pub focused: Option<Entity>,
+    /// set while a menu needs the navigation keys for itself
+    pub locked: bool,
+    /// focused entity the last time it was highlighted
+    highlighted: Option<Entity>,
+}
+
+#[derive(Event, Clone, Copy, PartialEq, Eq)]
+pub enum FocusInput {
+    Previous,
+    Next,
+    Confirm,
+    /// press the menu's Back button, if it has one
+    Back,
+}
+
+// ==== SYSTEMS ====
+
+/// Menu keys are the menu actions of InputBindings, rebinding can't leave one of them without a key
+pub fn read_keyboard_focus_input(
+    mut event_writer: EventWriter<FocusInput>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<InputBindings>,
+) {
+    if bindings.just_pressed(InputAction::MenuUp, &keyboard_input) {
+        event_writer.send(FocusInput::Previous);
+    }
+    if bindings.just_pressed(InputAction::MenuDown, &keyboard_input) {
+        event_writer.send(FocusInput::Next);
+    }
+    if bindings.just_pressed(InputAction::MenuConfirm, &keyboard_input) {
+        event_writer.send(FocusInput::Confirm);
+    }
+    if bindings.just_pressed(InputAction::MenuBack, &keyboard_input) {
+        event_writer.send(FocusInput::Back);
+    }
+}
+
+pub fn read_gamepad_focus_input(
+    mut event_writer: EventWriter<FocusInput>,
+    gamepads: Res<Gamepads>,
+    pad_input: Res<ButtonInput<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    bindings: Res<InputBindings>,
+    mut last_stick: Local<i8>,
+) {
+    if bindings.pad_just_pressed(InputAction::MenuUp, &gamepads, &pad_input) {
+        event_writer.send(FocusInput::Previous);
+    }
+    if bindings.pad_just_pressed(InputAction::MenuDown, &gamepads, &pad_input) {
+        event_writer.send(FocusInput::Next);
+    }
+    if bindings.pad_just_pressed(InputAction::MenuConfirm, &gamepads, &pad_input) {
+        event_writer.send(FocusInput::Confirm);
+    }
+    if bindings.pad_just_pressed(InputAction::MenuBack, &gamepads, &pad_input) {
+        event_writer.send(FocusInput::Back);
+    }
+    // the stick only moves the focus when it's tilted, not while it's held
+    let stick_y = gamepads.iter()
+        .filter_map(|pad| axes.get(GamepadAxis::new(pad, GamepadAxisType::LeftStickY)))
+        .fold(0.0, |a, b| if b.abs() > f32::abs(a) {b} else {a});
+    let stick = if stick_y > STICK_DEADZONE {1} else if stick_y < -STICK_DEADZONE {-1} else {0};
+    if stick != *last_stick {
+        match stick {
+            1 => {event_writer.send(FocusInput::Previous);},
+            -1 => {event_writer.send(FocusInput::Next);},
+            _ => {}
+        }
+        *last_stick = stick;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn move_menu_focus(
+    mut event_reader: EventReader<FocusInput>,
+    mut focus: ResMut<MenuFocus>,
+    mut button_query: Query<(Entity, &GlobalTransform, &ViewVisibility, &mut Interaction), With<Button>>,
+    action_query: Query<&MenuButton>,
+    hovered_query: Query<(Entity, &Interaction), (With<Button>, Changed<Interaction>)>,
+) {
+    // the mouse moves the focus as well, so keys continue from the hovered button
+    for (entity, interaction) in hovered_query.iter() {
+        if *interaction == Interaction::Hovered {
+            focus.focused = Some(entity);
+        }
+    }
+    // buttons in reading order, top to bottom then left to right
+    let mut buttons: Vec<(Entity, Vec3)> = button_query.iter()
+        .filter(|(_, _, visibility, _)| visibility.get())
+        .map(|(e, t, _, _)| (e, t.translation()))
+        .collect();
+    buttons.sort_by(|a, b| a.1.y.total_cmp(&b.1.y).then(a.1.x.total_cmp(&b.1.x)));
+    // forget buttons which were despawned with their menu
+    let index = focus.focused.and_then(|f| buttons.iter().position(|(e, _)| *e == f));
+    if index.is_none() {
+        focus.focused = None;
+    }
+    for event in event_reader.read() {
+        if buttons.is_empty() || focus.locked {
+            continue;
+        }
+        let index = focus.focused.and_then(|f| buttons.iter().position(|(e, _)| *e == f));
+        if *event == FocusInput::Back {
+            let back = buttons.iter().find(|(e, _)| action_query.get(*e).is_ok_and(|b| b.0 == MenuAction::Back));
+            if let Some(Ok((_, _, _, mut interaction))) = back.map(|(e, _)| button_query.get_mut(*e)) {
+                *interaction = Interaction::Pressed;
+            }
+            continue;
+        }
+        match (event, index) {
+            (FocusInput::Previous, Some(i)) => focus.focused = Some(buttons[(i + buttons.len() - 1) % buttons.len()].0),
+            (FocusInput::Next, Some(i)) => focus.focused = Some(buttons[(i + 1) % buttons.len()].0),
+            (_, None) => focus.focused = Some(buttons[0].0),
+            (FocusInput::Back, _) => {}
+            (FocusInput::Confirm, Some(i)) => {
+                if let Ok((_, _, _, mut interaction)) = button_query.get_mut(buttons[i].0) {
+                    // always flag as changed, so pressing the same button twice works
+                    *interaction = Interaction::Pressed;
+                }
+            }
+        }
+    }
+}
+
+pub fn highlight_menu_focus(
+    mut focus: ResMut<MenuFocus>,
+    mut button_query: Query<(&Interaction, &mut BackgroundColor), With<Button>>,
+) {
+    // unhighlight the previously focused button
+    if focus.focused != focus.highlighted {
+        if let Some(Ok((interaction, mut color))) = focus.highlighted.map(|e| button_query.get_mut(e)) {
+            if *interaction == Interaction::None {
+                *color = NORMAL_BUTTON_COLOR.into();
+            }
+        }
+        focus.highlighted = focus.focused;
+    }
+    // keep the focused one highlighted, even after the mouse left it
+    if let Some(Ok((interaction, mut color))) = focus.focused.map(|e| button_query.get_mut(e)) {
+        if *interaction == Interaction::None && color.0 != HOVERED_BUTTON_COLOR {
+            *color = HOVERED_BUTTON_COLOR.into();
+        }
+    }
+}
@@ -1,49 +1,84 @@
 use bevy::{app::AppExit, prelude::*};
 
-use crate::{game::{LoadRunEvent, RunData}, AppState, HudState, SimulationState};
-
-use super::styles::{HOVERED_BUTTON_COLOR, NORMAL_BUTTON_COLOR, PRESSED_BUTTON_COLOR};
-
-pub mod menu_buttons {
-    use bevy::prelude::Component;
-
-    #[derive(Component)]
-    pub struct PlayButtonComponent;
-
-    #[derive(Component)]
-    pub struct ExitButtonComponent;
+use crate::{editor::Playtest, audio::{PlaySoundEvent, Sound}, data::{InputAction, InputBindings, SettingKind, Settings, PATH_CONTROLS, PATH_SETTINGS}, game::{LoadRunEvent, RunData, SelectedRun}, AppState, HudState, SimulationState};
+
+use super::{controls_menu::RebindingAction, styles::{HOVERED_BUTTON_COLOR, NORMAL_BUTTON_COLOR, PRESSED_BUTTON_COLOR}};
+
+// ==== ACTIONS ====
+
+/// What a menu button does when pressed
+#[derive(Clone, PartialEq, Debug)]
+pub enum MenuAction {
+    /// open the run selection
+    Play,
+    Exit,
+    /// close the pause menu
+    Resume,
+    /// leave the game for the main menu
+    MainMenu,
+    /// go back to the menu a sub menu was opened from
+    Back,
+    /// start the current run again
+    Replay,
+    /// start the run at the given path
+    LoadRun(String),
+    OpenControls,
+    /// wait for a new key for the action
+    Rebind(InputAction),
+    ResetControls,
+    /// open settings from the main or the pause menu
+    OpenSettings,
+    /// switch an option to its next value
+    ChangeSetting(SettingKind),
+    /// edit the levels of the selected run
+    OpenEditor,
+}
 
-    #[derive(Component)]
-    pub struct ResumeButtonComponent;
+impl MenuAction {
+    /// parse `<ACTION> [argument]` as written in menu files
+    pub fn parse(segs: &[&str]) -> Option<MenuAction> {
+        Some(match *segs.first()? {
+            "PLAY" => MenuAction::Play,
+            "EXIT" => MenuAction::Exit,
+            "RESUME" => MenuAction::Resume,
+            "MAIN_MENU" => MenuAction::MainMenu,
+            "BACK" => MenuAction::Back,
+            "REPLAY" => MenuAction::Replay,
+            "LOAD_RUN" => MenuAction::LoadRun(segs.get(1)?.to_string()),
+            "OPEN_CONTROLS" => MenuAction::OpenControls,
+            "REBIND" => MenuAction::Rebind(InputAction::from_name(segs.get(1)?)?),
+            "RESET_CONTROLS" => MenuAction::ResetControls,
+            "OPEN_SETTINGS" => MenuAction::OpenSettings,
+            "CHANGE_SETTING" => MenuAction::ChangeSetting(SettingKind::from_name(segs.get(1)?)?),
+            "OPEN_EDITOR" => MenuAction::OpenEditor,
+            _ => return None,
+        })
+    }
+}
 
-    #[derive(Component)]
-    pub struct MainMenuButtonComponent;
+#[derive(Component)]
+pub struct MenuButton(pub MenuAction);
 
-    #[derive(Component)]
-    pub struct BackButtonComponent;
+#[derive(Event)]
+pub struct MenuActionEvent(pub MenuAction);
 
-    #[derive(Component)]
-    pub struct ReplayButtonComponent;
-}
+// ==== SYSTEMS ====
 
-pub fn interact_with_play_button(
-    mut button_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (
-            Changed<Interaction>,
-            With<menu_buttons::PlayButtonComponent>,
-        ),
-    >,
-    mut app_state_next_state: ResMut<NextState<AppState>>,
+/// Colours every menu button by its interaction and sends its action when pressed
+pub fn interact_with_menu_button(
+    mut button_query: Query<(&Interaction, &mut BackgroundColor, &MenuButton), Changed<Interaction>>,
+    mut event_writer: EventWriter<MenuActionEvent>,
+    mut sounds: EventWriter<PlaySoundEvent>,
 ) {
-    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
+    for (interaction, mut background_color, button) in button_query.iter_mut() {
         match *interaction {
             Interaction::Hovered => {
                 *background_color = HOVERED_BUTTON_COLOR.into();
             }
             Interaction::Pressed => {
                 *background_color = PRESSED_BUTTON_COLOR.into();
-                app_state_next_state.set(AppState::Game);
+                event_writer.send(MenuActionEvent(button.0.clone()));
+                sounds.send(PlaySoundEvent::new(Sound::MenuClick));
             }
             Interaction::None => {
                 *background_color = NORMAL_BUTTON_COLOR.into();
@@ -52,144 +87,84 @@ pub fn interact_with_play_button(
     }
 }
 
-pub fn interact_with_exit_button(
-    mut button_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (
-            Changed<Interaction>,
-            With<menu_buttons::ExitButtonComponent>,
-        ),
-    >,
+#[allow(clippy::too_many_arguments)]
+pub fn handle_menu_action(
+    mut event_reader: EventReader<MenuActionEvent>,
+    app_state: Res<State<AppState>>,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+    mut hud_next_state: ResMut<NextState<HudState>>,
+    mut simulation_next_state: ResMut<NextState<SimulationState>>,
     mut app_exit_event_writer: EventWriter<AppExit>,
+    mut load_run_event_writer: EventWriter<LoadRunEvent>,
+    run_resource: Res<RunData>,
+    mut selected_run: ResMut<SelectedRun>,
+    mut bindings: ResMut<InputBindings>,
+    mut rebinding: ResMut<RebindingAction>,
+    mut settings: ResMut<Settings>,
+    playtest: Option<Res<Playtest>>,
 ) {
-    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
-        match *interaction {
-            Interaction::Hovered => {
-                *background_color = HOVERED_BUTTON_COLOR.into();
+    let in_game = *app_state.get() == AppState::Game;
+    for event in event_reader.read() {
+        match &event.0 {
+            MenuAction::Play => {
+                app_state_next_state.set(AppState::Levels);
             }
-            Interaction::Pressed => {
-                *background_color = PRESSED_BUTTON_COLOR.into();
+            MenuAction::Exit => {
                 app_exit_event_writer.send(AppExit);
             }
-            Interaction::None => {
-                *background_color = NORMAL_BUTTON_COLOR.into();
-            }
-        }
-    }
-}
-
-pub fn interact_with_main_menu_button(
-    mut button_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (
-            Changed<Interaction>,
-            With<menu_buttons::MainMenuButtonComponent>,
-        ),
-    >,
-    mut app_state_next_state: ResMut<NextState<AppState>>,
-    mut hud_next_state: ResMut<NextState<HudState>>,
-) {
-    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
-        match *interaction {
-            Interaction::Hovered => {
-                *background_color = HOVERED_BUTTON_COLOR.into();
+            MenuAction::Resume => {
+                simulation_next_state.set(SimulationState::Running);
+                hud_next_state.set(HudState::None);
             }
-            Interaction::Pressed => {
-                *background_color = PRESSED_BUTTON_COLOR.into();
-                app_state_next_state.set(AppState::MainMenu);
+            MenuAction::MainMenu => {
+                // a playtest goes back to the editor it was started from
+                app_state_next_state.set(if playtest.is_some() {AppState::Editor} else {AppState::MainMenu});
                 hud_next_state.set(HudState::None);
             }
-            Interaction::None => {
-                *background_color = NORMAL_BUTTON_COLOR.into();
+            MenuAction::Back => {
+                if in_game {
+                    hud_next_state.set(HudState::Pause);
+                } else {
+                    app_state_next_state.set(AppState::MainMenu);
+                }
             }
-        }
-    }
-}
-
-pub fn interact_with_resume_button(
-    mut button_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (
-            Changed<Interaction>,
-            With<menu_buttons::ResumeButtonComponent>,
-        ),
-    >,
-    mut simulation_state_next_state: ResMut<NextState<SimulationState>>,
-    mut hud_next_state: ResMut<NextState<HudState>>,
-) {
-    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
-        match *interaction {
-            Interaction::Hovered => {
-                *background_color = HOVERED_BUTTON_COLOR.into();
+            MenuAction::Replay => {
+                load_run_event_writer.send(LoadRunEvent {
+                    path: run_resource.path.to_owned(),
+                });
+                hud_next_state.set(HudState::None);
+                simulation_next_state.set(SimulationState::Running);
             }
-            Interaction::Pressed => {
-                *background_color = PRESSED_BUTTON_COLOR.into();
-                simulation_state_next_state.set(SimulationState::Running);
-                hud_next_state.set(HudState::None)
+            MenuAction::LoadRun(path) => {
+                selected_run.0 = path.to_owned();
+                app_state_next_state.set(AppState::Game);
             }
-            Interaction::None => {
-                *background_color = NORMAL_BUTTON_COLOR.into();
+            MenuAction::OpenControls => {
+                app_state_next_state.set(AppState::Controls);
             }
-        }
-    }
-}
-
-pub fn interact_with_back_button(
-    mut button_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (
-            Changed<Interaction>,
-            With<menu_buttons::BackButtonComponent>,
-        ),
-    >,
-    mut app_state_next_state: ResMut<NextState<AppState>>,
-) {
-    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
-        match *interaction {
-            Interaction::Hovered => {
-                *background_color = HOVERED_BUTTON_COLOR.into();
+            MenuAction::Rebind(action) => {
+                rebinding.action = Some(*action);
+                rebinding.conflict = None;
             }
-            Interaction::Pressed => {
-                *background_color = PRESSED_BUTTON_COLOR.into();
-                app_state_next_state.set(AppState::MainMenu);
+            MenuAction::ResetControls => {
+                *bindings = InputBindings::default();
+                bindings.save(PATH_CONTROLS);
+                *rebinding = RebindingAction::default();
             }
-            Interaction::None => {
-                *background_color = NORMAL_BUTTON_COLOR.into();
-            }
-        }
-    }
-}
-
-pub fn interact_with_replay_button(
-    mut button_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (
-            Changed<Interaction>,
-            With<menu_buttons::ReplayButtonComponent>,
-        ),
-    >,
-    mut event_write: EventWriter<LoadRunEvent>,
-    run_resource: Res<RunData>,
-    mut hud_next_state: ResMut<NextState<HudState>>,
-    mut simulation_next_state: ResMut<NextState<SimulationState>>
-) {
-    if let Ok((interaction, mut background_color)) = button_query.get_single_mut() {
-        match *interaction {
-            Interaction::Hovered => {
-                *background_color = HOVERED_BUTTON_COLOR.into();
+            MenuAction::OpenSettings => {
+                if in_game {
+                    hud_next_state.set(HudState::Settings);
+                } else {
+                    app_state_next_state.set(AppState::Settings);
+                }
             }
-            Interaction::Pressed => {
-                *background_color = PRESSED_BUTTON_COLOR.into();
-                event_write.send(LoadRunEvent{
-                    path: run_resource.path.to_owned()
-                });
-                hud_next_state.set(HudState::None);
-                simulation_next_state.set(SimulationState::Running)
+            MenuAction::ChangeSetting(kind) => {
+                kind.cycle(&mut settings);
+                settings.save(PATH_SETTINGS);
             }
-            Interaction::None => {
-                *background_color = NORMAL_BUTTON_COLOR.into();
+            MenuAction::OpenEditor => {
+                app_state_next_state.set(AppState::Editor);
             }
         }
     }
 }
-
@@ -1,80 +1,58 @@
 use bevy::prelude::*;
 
-use crate::{data::get_levels_data, menu::interactions::menu_buttons, AppState};
+use crate::{data::{get_levels_data, Leaderboard, SaveData}, AppState};
 
-
-
-use super::{interactions::interact_with_back_button, styles::*};
+use super::{
+    builder::{despawn_menu, MenuBuilder},
+    interactions::MenuAction,
+    score_menu::{format_time, leaderboard_rows},
+    styles::*,
+};
 
 pub struct LevelsMenuPlugin;
 
-#[allow(dead_code,path_statements)]
 impl Plugin for LevelsMenuPlugin {
     fn build(&self, app: &mut App) {
         app
         //
         .add_systems(OnEnter(AppState::Levels), spawn_levels_menu)
-        .add_systems(Update,interact_with_back_button.run_if(in_state(AppState::Levels)))
-        .add_systems(OnExit(AppState::Levels), despawn_levels_menu)
+        .add_systems(OnExit(AppState::Levels), despawn_menu("levels"))
         //
         ;
     }
 }
 
-
-#[derive(Component)]
-pub struct LevelsMenuComponent;
-
-pub fn spawn_levels_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let _main_menu_entity = build_levels_menu(&mut commands, &asset_server);
+pub fn spawn_levels_menu(mut commands: Commands, asset_server: Res<AssetServer>, save: Res<SaveData>) {
+    let _main_menu_entity = build_levels_menu(&save).spawn(&mut commands, &asset_server);
 }
 
-pub fn despawn_levels_menu(
-    mut commands: Commands,
-    main_menu_query: Query<Entity, With<LevelsMenuComponent>>,
-) {
-    if let Ok(main_menu_entity) = main_menu_query.get_single() {
-        commands.entity(main_menu_entity).despawn_recursive();
-    }
-}
-
-pub fn build_levels_menu(commands: &mut Commands, asset_server: &Res<AssetServer>) -> Entity {
-    let game_ui_entity = commands
-        .spawn((
-            NodeBundle {
-                style: PAUSE_MENU_STYLE,
-                ..default()
+/// top entries of a run's leaderboard shown under its button
+pub const LEVELS_MENU_LEADERBOARD: usize = 3;
+
+/// One button per run found in assets/levels, with its best time or how far it was played,
+/// followed by the top of its leaderboard
+pub fn build_levels_menu(save: &SaveData) -> MenuBuilder {
+    let mut menu = MenuBuilder::new("levels", PAUSE_MENU_STYLE);
+    for run in get_levels_data() {
+        let progress = match save.runs.get(&run.path) {
+            Some(saved) => match saved.run.best_time {
+                Some(best) if saved.run.completed => match saved.run.medal {
+                    Some(medal) => format!(" - {} {}", format_time(best), medal.label()),
+                    None => format!(" - {}", format_time(best)),
+                },
+                _ => format!(" - {}/{}", saved.completed_levels(), run.amount),
             },
-            LevelsMenuComponent {},
-        ))
-        .with_children(|parent| {
-            // Play Button
-            parent
-                .spawn((
-                    ButtonBundle {
-                        style: NORMAL_BUTTON_STYLE,
-                        background_color: NORMAL_BUTTON_COLOR.into(),
-                        ..default()
-                    },
-                    menu_buttons::BackButtonComponent,
-                ))
-                .with_children(|parent| {
-                    parent.spawn(TextBundle {
-                        text: Text {
-                            sections: vec![TextSection::new(
-                                "Resume",
-                                get_normal_text_style(&asset_server),
-                            )],
-                            justify: JustifyText::Center,
-                            ..default()
-                        },
-                        ..default()
-                    });
-                });
-
-        // end
-        })
-        .id();
-    println!("{:?}",get_levels_data());
-    game_ui_entity
+            None => String::new(),
+        };
+        let leaderboard = leaderboard_rows(&Leaderboard::load(&run.path));
+        menu = menu.wide_button(
+            format!("{} by {} ({}){}", run.name, run.author, run.amount, progress),
+            MenuAction::LoadRun(run.path),
+        );
+        if !leaderboard.is_empty() {
+            let top = leaderboard.len().min(LEVELS_MENU_LEADERBOARD);
+            menu = menu.text(leaderboard[..top].join("  "));
+        }
+    }
+    menu.button("Back", MenuAction::Back)
 }
@@ -1,9 +1,6 @@
 use bevy::prelude::*;
 
-use super::{
-    interactions::{interact_with_exit_button, interact_with_play_button, menu_buttons},
-    styles::*,
-};
+use super::builder::{despawn_menu, MenuBuilder};
 use crate::AppState;
 
 pub struct MainMenuPlugin;
@@ -13,89 +10,13 @@ impl Plugin for MainMenuPlugin {
         app
             //
             .add_systems(OnEnter(AppState::MainMenu), spawn_main_menu)
-            .add_systems(Update, (interact_with_play_button,interact_with_exit_button).run_if(in_state(AppState::MainMenu)))
-            .add_systems(OnExit(AppState::MainMenu), despawn_main_menu)
+            .add_systems(OnExit(AppState::MainMenu), despawn_menu("main"))
             // .
             ;
     }
 }
 
-#[derive(Component)]
-pub struct MainMenuComponent;
-
-
-
+/// described by assets/menus/main
 pub fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let _main_menu_entity = build_main_menu(&mut commands, &asset_server);
-}
-
-pub fn despawn_main_menu(
-    mut commands: Commands,
-    main_menu_query: Query<Entity, With<MainMenuComponent>>,
-) {
-    if let Ok(main_menu_entity) = main_menu_query.get_single() {
-        commands.entity(main_menu_entity).despawn_recursive();
-    }
-}
-
-pub fn build_main_menu(commands: &mut Commands, asset_server: &Res<AssetServer>) -> Entity {
-    let game_ui_entity = commands
-        .spawn((
-            NodeBundle {
-                style: MAIN_MENU_STYLE,
-                ..default()
-            },
-            MainMenuComponent,
-        ))
-        .with_children(|parent| {
-            // Play Button
-            parent
-                .spawn((
-                    ButtonBundle {
-                        style: NORMAL_BUTTON_STYLE,
-                        background_color: NORMAL_BUTTON_COLOR.into(),
-                        ..default()
-                    },
-                    menu_buttons::PlayButtonComponent,
-                ))
-                .with_children(|parent| {
-                    parent.spawn(TextBundle {
-                        text: Text {
-                            sections: vec![TextSection::new(
-                                "Play",
-                                get_normal_text_style(&asset_server),
-                            )],
-                            justify: JustifyText::Center,
-                            ..default()
-                        },
-                        ..default()
-                    });
-                });
-            // Exit Button
-            parent
-                .spawn((
-                    ButtonBundle {
-                        style: NORMAL_BUTTON_STYLE,
-                        background_color: NORMAL_BUTTON_COLOR.into(),
-                        ..default()
-                    },
-                    menu_buttons::ExitButtonComponent,
-                ))
-                .with_children(|parent| {
-                    parent.spawn(TextBundle {
-                        text: Text {
-                            sections: vec![TextSection::new(
-                                "Exit",
-                                get_normal_text_style(&asset_server),
-                            )],
-                            justify: JustifyText::Center,
-                            ..default()
-                        },
-                        ..default()
-                    });
-                });
-            // end
-        })
-        .id();
-    game_ui_entity
+    let _main_menu_entity = MenuBuilder::load("main").spawn(&mut commands, &asset_server);
 }
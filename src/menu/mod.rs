@@ -1,13 +1,21 @@
+pub(crate) mod builder;
+mod controls_menu;
+pub(crate) mod focus;
 mod levels_menu;
 mod main_menu;
 mod score_menu;
+mod settings_menu;
 pub(crate) mod styles;
 pub(crate) mod interactions;
 
 use bevy::prelude::*;
+use interactions::{handle_menu_action, interact_with_menu_button, MenuActionEvent};
+use controls_menu::ControlsMenuPlugin;
+use focus::FocusPlugin;
 use levels_menu::LevelsMenuPlugin;
 use main_menu::MainMenuPlugin;
 use score_menu::ScoreMenuPlugin;
+use settings_menu::SettingsMenuPlugin;
 pub(crate) use score_menu::SpawnScoreMenuEvent;
 
 pub struct MenuPLugin;
@@ -16,7 +24,9 @@ impl Plugin for MenuPLugin {
     fn build(&self, app: &mut App) {
         app
         //
-        .add_plugins((MainMenuPlugin,LevelsMenuPlugin,ScoreMenuPlugin))
+        .add_event::<MenuActionEvent>()
+        .add_plugins((MainMenuPlugin,LevelsMenuPlugin,ScoreMenuPlugin,ControlsMenuPlugin,SettingsMenuPlugin,FocusPlugin))
+        .add_systems(Update, (interact_with_menu_button, handle_menu_action).chain())
         //
         ;
     }
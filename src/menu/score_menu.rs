@@ -1,17 +1,26 @@
+use std::fs;
+
 use bevy::prelude::*;
 
 use crate::{
-    menu::styles::{
-        get_normal_text_style, NORMAL_BUTTON_COLOR, NORMAL_BUTTON_STYLE, PAUSE_MENU_STYLE,
-    },
+    editor::Playtest,
+    data::{Leaderboard, LeaderboardEntry, RunSave, PLAYER_NAME_LENGTH},
+    game::{new_replay_file, Medal, Replay, PATH_REPLAYS},
     AppState, HudState, SimulationState,
 };
 
 use super::{
-    interactions::{interact_with_main_menu_button, interact_with_replay_button, menu_buttons},
-    styles::get_title_text_style,
+    builder::{despawn_menu, MenuBuilder, MenuRoot},
+    focus::MenuFocus,
+    interactions::MenuAction,
+    styles::{get_normal_text_style, get_title_text_style, SETTINGS_MENU_STYLE},
 };
 
+/// time (in seconds) before the medal shows up
+pub const MEDAL_REVEAL_DELAY: f32 = 0.5;
+/// time (in seconds) of the medal growing into place
+pub const MEDAL_REVEAL_TIME: f32 = 0.4;
+
 pub struct ScoreMenuPlugin;
 
 impl Plugin for ScoreMenuPlugin {
@@ -19,120 +28,160 @@ impl Plugin for ScoreMenuPlugin {
         app
     // .
         .add_event::<SpawnScoreMenuEvent>()
-        .add_systems(Update, handle_spawn_score_menu_event
-            .run_if(in_state(AppState::Game))
-        )
-        .add_systems(Update, (interact_with_replay_button,interact_with_main_menu_button)
+        .add_systems(Update, (handle_spawn_score_menu_event, animate_medal_reveal, type_player_name)
             .run_if(in_state(AppState::Game))
-            .run_if(in_state(HudState::Score))
         )
-        .add_systems(OnExit(HudState::Score), despawn_score_menu)
+        .add_systems(OnExit(HudState::Score), (despawn_menu("score"), close_score_screen))
     // .
     ;
     }
 }
 
-#[derive(Event)]
+#[derive(Event, Clone)]
 pub struct SpawnScoreMenuEvent {
+    /// path of the run
+    pub run: String,
     pub points: usize,
     pub time: f32,
+    /// run time at the end of each level
+    pub splits: Vec<f32>,
+    /// par time of each level, from the run's info file
+    pub par: Vec<Option<f32>>,
+    pub deaths: usize,
+    pub medal: Option<Medal>,
+    /// results of the run before this finish
+    pub previous: RunSave,
 }
 
+/// Finished run shown on the score screen, kept to rebuild it once a name was entered
+#[derive(Resource)]
+pub struct ScoreScreen {
+    pub score: SpawnScoreMenuEvent,
+    /// name being typed, while the run makes the leaderboard
+    pub name: Option<String>,
+}
+
+/// Name typed for the leaderboard
 #[derive(Component)]
-pub struct ScoreMenuComponent;
+pub struct NameInput;
 
-// handled by handle_spawn_score_menu_event
-// pub fn spawn_score_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
-//     let _main_menu_entity = build_score_menu(&mut commands, &asset_server);
-// }
+/// seconds shown with two decimals, e.g. "12.34s"
+pub fn format_time(time: f32) -> String {
+    ((time * 100.0).floor() / 100.0).to_string() + "s"
+}
 
-pub fn despawn_score_menu(
-    mut commands: Commands,
-    main_menu_query: Query<Entity, With<ScoreMenuComponent>>,
-) {
-    if let Ok(main_menu_entity) = main_menu_query.get_single() {
-        commands.entity(main_menu_entity).despawn_recursive();
+/// difference to another time with its sign, e.g. "-0.52"
+pub fn format_delta(delta: f32) -> String {
+    let sign = if delta < 0.0 {"-"} else {"+"};
+    format!("{}{:.2}", sign, delta.abs())
+}
+
+/// One row per level: its time, the split compared to the personal best and the par time
+fn split_rows(splits: &[f32], par: &[Option<f32>], best_splits: &[f32]) -> Vec<String> {
+    splits.iter().enumerate().map(|(n, &split)| {
+        let time = split - if n > 0 {splits[n - 1]} else {0.0};
+        let mut row = format!("{}. {}", n + 1, format_time(time));
+        if let Some(best) = best_splits.get(n) {
+            row += &format!("  {}", format_delta(split - best));
+        }
+        if let Some(Some(par)) = par.get(n) {
+            row += &format!("  par {}", format_time(*par));
+        }
+        row
+    }).collect()
+}
+
+pub fn build_score_menu(event: &SpawnScoreMenuEvent) -> MenuBuilder {
+    let (points, time, previous) = (event.points, event.time, &event.previous.run);
+    let time_label = match previous.best_time {
+        Some(best) if best <= time => format!("Best {}", format_time(best)),
+        Some(best) => format!("New best! ({} faster)", format_time(best - time)),
+        None => "New best!".to_string(),
+    };
+    let coins_label = if points > previous.max_coins && previous.completed {
+        format!("{} coins, new best!", points)
+    } else {
+        format!("{} coins", points)
+    };
+    let coins_label = format!("{}, {} deaths", coins_label, event.deaths);
+    // wraps into a second column once the splits and the leaderboard don't fit
+    let mut menu = MenuBuilder::new("score", SETTINGS_MENU_STYLE)
+        .title(format_time(time))
+        .text(time_label)
+        .text(coins_label);
+    for row in split_rows(&event.splits, &event.par, &event.previous.splits) {
+        menu = menu.text(row);
     }
+    menu
+}
+
+/// One row per entry, e.g. "1. name 12.34s"
+pub fn leaderboard_rows(leaderboard: &Leaderboard) -> Vec<String> {
+    leaderboard.entries.iter().enumerate()
+        .map(|(n, entry)| format!("{}. {} {}", n + 1, entry.name, format_time(entry.time)))
+        .collect()
 }
 
-pub fn build_score_menu(
-    _points: usize,
-    time: f32,
+/// Spawn the score menu with the run's leaderboard, and a name input while a name is being typed
+pub fn spawn_score_menu(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
-) -> Entity {
-    let game_ui_entity = commands
-        .spawn((
-            NodeBundle {
-                style: PAUSE_MENU_STYLE,
-                ..default()
-            },
-            ScoreMenuComponent {},
-        ))
-        .with_children(|parent| {
-            // Time
-            parent.spawn((TextBundle {
-                text: Text {
-                    sections: vec![TextSection::new(
-                        ((time * 100.0).floor() / 100.0).to_string()+"s",
-                        get_title_text_style(&asset_server),
-                    )],
-                    justify: JustifyText::Center,
-                    linebreak_behavior: bevy::text::BreakLineOn::NoWrap,
-                },
-                ..default()
-            },));
-            // Resume Button
-            parent
-                .spawn((
-                    ButtonBundle {
-                        style: NORMAL_BUTTON_STYLE,
-                        background_color: NORMAL_BUTTON_COLOR.into(),
-                        ..default()
-                    },
-                    menu_buttons::ReplayButtonComponent,
-                ))
-                .with_children(|parent| {
-                    parent.spawn(TextBundle {
-                        text: Text {
-                            sections: vec![TextSection::new(
-                                "Replay",
-                                get_normal_text_style(&asset_server),
-                            )],
-                            justify: JustifyText::Center,
-                            ..default()
-                        },
-                        ..default()
-                    });
-                });
-            // Replay Button
-            parent
-                .spawn((
-                    ButtonBundle {
-                        style: NORMAL_BUTTON_STYLE,
-                        background_color: NORMAL_BUTTON_COLOR.into(),
-                        ..default()
-                    },
-                    menu_buttons::MainMenuButtonComponent,
-                ))
-                .with_children(|parent| {
-                    parent.spawn(TextBundle {
-                        text: Text {
-                            sections: vec![TextSection::new(
-                                "Exit",
-                                get_normal_text_style(&asset_server),
-                            )],
-                            justify: JustifyText::Center,
-                            ..default()
-                        },
-                        ..default()
-                    });
-                });
-
-            // end
-        })
-        .id();
-    game_ui_entity
+    score: &SpawnScoreMenuEvent,
+    name: Option<&str>,
+) {
+    let mut menu = build_score_menu(score);
+    let name_index = menu.items.len();
+    let leaderboard = Leaderboard::load(&score.run);
+    if !leaderboard.entries.is_empty() {
+        menu = menu.text("Leaderboard");
+    }
+    for row in leaderboard_rows(&leaderboard) {
+        menu = menu.text(row);
+    }
+    let score_menu_entity = menu
+        .button("Replay", MenuAction::Replay)
+        .button("Exit", MenuAction::MainMenu)
+        .spawn(commands, asset_server);
+    let mut children = Vec::new();
+    if let Some(medal) = score.medal {
+        // right under the time
+        children.push((1, spawn_medal(commands, medal, asset_server)));
+    }
+    if let Some(name) = name {
+        // right above the leaderboard, after the medal moved it down
+        let name_entity = commands.spawn((
+            TextBundle::from_section(name_label(name), get_normal_text_style(asset_server)),
+            NameInput,
+        )).id();
+        children.push((name_index + children.len(), name_entity));
+    }
+    for (index, child) in children {
+        commands.entity(score_menu_entity).insert_children(index, &[child]);
+    }
+}
+
+fn name_label(name: &str) -> String {
+    format!("New record! Name: {}_", name)
+}
+
+/// Add the finished run to its leaderboard, saving its replay and deleting the ones which fell off
+fn submit_leaderboard_entry(score: &SpawnScoreMenuEvent, name: String, replay: &Replay) {
+    let mut leaderboard = Leaderboard::load(&score.run);
+    let replay_file = new_replay_file(&score.run);
+    let dropped = leaderboard.insert(LeaderboardEntry {
+        name,
+        time: score.time,
+        coins: score.points,
+        deaths: score.deaths,
+        replay: replay_file.clone(),
+    });
+    for entry in dropped.iter().filter(|e| e.replay != replay_file) {
+        let _ = fs::remove_file(format!("{}{}", PATH_REPLAYS, entry.replay));
+    }
+    if !dropped.iter().any(|e| e.replay == replay_file) {
+        replay.save(&format!("{}{}", PATH_REPLAYS, replay_file));
+    }
+    leaderboard.save(&score.run);
 }
 
 pub fn handle_spawn_score_menu_event(
@@ -141,14 +190,115 @@ pub fn handle_spawn_score_menu_event(
     asset_server: Res<AssetServer>,
     mut simulation_next_state: ResMut<NextState<SimulationState>>,
     mut hud_next_state: ResMut<NextState<HudState>>,
+    playtest: Option<Res<Playtest>>,
 ) {
     match event_reader.read().last() {
         None => {}
+        // playtests go straight back to the editor
+        Some(_) if playtest.is_some() => {}
         Some(event) => {
-            let _main_menu_entity =
-                build_score_menu(event.points, event.time, &mut commands, &asset_server);
+            // only runs making the leaderboard ask for a name
+            let name = Leaderboard::load(&event.run).rank(event.time).map(|_| String::new());
+            spawn_score_menu(&mut commands, &asset_server, event, name.as_deref());
+            commands.insert_resource(ScoreScreen {
+                score: event.clone(),
+                name,
+            });
             simulation_next_state.set(SimulationState::Paused);
             hud_next_state.set(HudState::Score)
         }
     };
 }
+
+/// Type a name for the leaderboard, Enter adds the run to it
+#[allow(clippy::too_many_arguments)]
+pub fn type_player_name(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut char_reader: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    screen: Option<ResMut<ScoreScreen>>,
+    replay: Res<Replay>,
+    mut focus: ResMut<MenuFocus>,
+    mut name_query: Query<&mut Text, With<NameInput>>,
+    menu_query: Query<(Entity, &MenuRoot)>,
+) {
+    // read every frame, so keys held while playing don't end up in the name
+    let typed: String = char_reader.read().flat_map(|e| e.char.chars()).collect();
+    let Some(mut screen) = screen else {return};
+    let Some(name) = screen.name.as_mut() else {return};
+    // typed keys (Enter, arrows) shouldn't move through the buttons
+    focus.locked = true;
+    for c in typed.chars() {
+        if (c.is_alphanumeric() || c == ' ' || c == '-' || c == '_') && name.chars().count() < PLAYER_NAME_LENGTH {
+            name.push(c);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Backspace) {
+        name.pop();
+    }
+    if let Ok(mut text) = name_query.get_single_mut() {
+        let label = name_label(name);
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
+    }
+    if !keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) || name.trim().is_empty() {return;}
+    let name = name.trim().to_string();
+    println!("[LEADERBOARD] Adding {} with {}", name, format_time(screen.score.time));
+    submit_leaderboard_entry(&screen.score, name, &replay);
+    screen.name = None;
+    focus.locked = false;
+    // rebuild the menu with the new entry
+    for (entity, root) in menu_query.iter() {
+        if root.0 == "score" {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    spawn_score_menu(&mut commands, &asset_server, &screen.score, None);
+}
+
+pub fn close_score_screen(mut commands: Commands, mut focus: ResMut<MenuFocus>) {
+    commands.remove_resource::<ScoreScreen>();
+    focus.locked = false;
+}
+
+// ==== MEDAL ====
+
+/// Medal label popping up on the score screen
+#[derive(Component)]
+pub struct MedalReveal {
+    pub elapsed: f32,
+    pub color: Color,
+}
+
+fn spawn_medal(commands: &mut Commands, medal: Medal, asset_server: &Res<AssetServer>) -> Entity {
+    let mut style = get_title_text_style(asset_server);
+    style.color = Color::NONE;
+    commands
+        .spawn((
+            TextBundle {
+                text: Text::from_section(medal.label(), style),
+                transform: Transform::from_scale(Vec3::ZERO),
+                ..default()
+            },
+            MedalReveal { elapsed: 0.0, color: medal.color() },
+        ))
+        .id()
+}
+
+/// Fade the medal in while it grows past its size and settles back
+pub fn animate_medal_reveal(
+    mut medal_query: Query<(&mut Text, &mut Transform, &mut MedalReveal)>,
+    time: Res<Time<Real>>,
+) {
+    for (mut text, mut transform, mut reveal) in medal_query.iter_mut() {
+        if reveal.elapsed > MEDAL_REVEAL_DELAY + MEDAL_REVEAL_TIME {continue;}
+        reveal.elapsed += time.delta_seconds();
+        let t = ((reveal.elapsed - MEDAL_REVEAL_DELAY) / MEDAL_REVEAL_TIME).clamp(0.0, 1.0);
+        // overshoots to 1.3 halfway through
+        let scale = t * (1.0 + 0.6 * (1.0 - t));
+        transform.scale = Vec3::new(scale, scale, 1.0);
+        text.sections[0].style.color = reveal.color.with_a(t);
+    }
+}
@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+
+use crate::{
+    data::{SettingKind, Settings},
+    AppState, HudState,
+};
+
+use super::{
+    builder::{despawn_menu, MenuBuilder},
+    interactions::{MenuAction, MenuButton},
+    styles::*,
+};
+
+/// Settings menu, opened from the main menu (AppState::Settings) or the pause menu (HudState::Settings)
+pub struct SettingsMenuPlugin;
+
+impl Plugin for SettingsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        //
+        .add_systems(OnEnter(AppState::Settings), spawn_settings_menu)
+        .add_systems(OnEnter(HudState::Settings), spawn_settings_menu)
+        .add_systems(Update, update_setting_labels)
+        .add_systems(OnExit(AppState::Settings), despawn_menu("settings"))
+        .add_systems(OnExit(HudState::Settings), despawn_menu("settings"))
+        //
+        ;
+    }
+}
+
+pub fn spawn_settings_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+) {
+    let _main_menu_entity = build_settings_menu(&settings).spawn(&mut commands, &asset_server);
+}
+
+pub fn build_settings_menu(settings: &Settings) -> MenuBuilder {
+    let mut menu = MenuBuilder::new("settings", SETTINGS_MENU_STYLE);
+    for kind in SettingKind::ALL {
+        menu = menu.wide_button(kind.label(settings), MenuAction::ChangeSetting(kind));
+    }
+    menu.button("Back", MenuAction::Back)
+}
+
+pub fn update_setting_labels(
+    button_query: Query<(&MenuButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+    settings: Res<Settings>,
+) {
+    if !settings.is_changed() {return;}
+    for (button, children) in button_query.iter() {
+        let MenuAction::ChangeSetting(kind) = button.0 else {continue};
+        for &child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.sections[0].value = kind.label(&settings);
+            }
+        }
+    }
+}
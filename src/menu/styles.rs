@@ -17,6 +17,17 @@ pub const NORMAL_BUTTON_STYLE: Style = {
     style
 };
 
+pub const WIDE_BUTTON_STYLE: Style = {
+    let mut style = Style::DEFAULT;
+    style.align_content = AlignContent:: Center;
+    style.justify_content = JustifyContent::Center;
+    style.align_items = AlignItems::Center;
+    style.justify_items = JustifyItems::Center;
+    style.width = Val::Px(560.0);
+    style.height = Val::Px(80.0);
+    style
+};
+
 pub const NORMAL_IMAGE_STYLE: Style = {
     let mut style = Style::DEFAULT;
     style.width = Val::Px(64.0);
@@ -65,6 +76,21 @@ pub const MAIN_MENU_STYLE: Style = {
     style
 };
 
+/// like MAIN_MENU_STYLE, but long lists continue in another column
+pub const SETTINGS_MENU_STYLE: Style = {
+    let mut style = Style::DEFAULT;
+    style.width = Val::Percent(100.0);
+    style.height = Val::Percent(100.0);
+    style.flex_direction = FlexDirection::Column;
+    style.flex_wrap = FlexWrap::Wrap;
+    style.justify_content = JustifyContent::Center;
+    style.align_items = AlignItems::Center;
+    style.align_content = AlignContent::Center;
+    style.row_gap = Val::Px(8.0);
+    style.column_gap = Val::Px(8.0);
+    style
+};
+
 pub const PAUSE_MENU_STYLE: Style = {
     let mut style = Style::DEFAULT;
     style.width = Val::Percent(40.0);